@@ -30,9 +30,12 @@ fn start(
 ) {
     let mut rng = rand::thread_rng();
     let mut meshie = Mesh::from(shape::Cube { size: 100.0 });
+    let sphere = meshie
+        .bounding_sphere(attributes::vertex_range(&meshie).expect("cube ship vertices"))
+        .expect("measuring cube ship");
     let mut cubeships = vec![CubeShip {
-        position: vec3(0.0, 0.0, 0.0),
-        radius: 50.0,
+        position: sphere.center,
+        radius: sphere.radius,
     }];
 
     for _ in 0..10 {
@@ -42,14 +45,20 @@ fn start(
             rng.gen_range(-205.0, 205.0),
             rng.gen_range(-205.0, 205.0),
         );
-        let verts = meshie.add_mesh( &mesh);
-        meshie.translate_mesh( verts, position);
+        let verts = meshie.add_mesh(&mesh).expect("adding cube ship");
+        meshie
+            .translate_mesh(verts, position)
+            .expect("moving cube ship");
 
+        let sphere = meshie.bounding_sphere(verts).expect("measuring cube ship");
         cubeships.push(CubeShip {
-            position,
-            radius: 50.,
+            position: sphere.center,
+            radius: sphere.radius,
         });
     }
+    let sphere = meshie
+        .bounding_sphere(attributes::vertex_range(&meshie).expect("cube ship vertices"))
+        .expect("measuring cube ships");
     let mesh_handle = meshes.add(meshie);
     let mat_handle = materials.add(StandardMaterial::default());
     commands
@@ -60,7 +69,7 @@ fn start(
         })
         .with(MeshieMarker {
             cubeships,
-            radius: 200.0,
+            radius: sphere.center.length() + sphere.radius,
         });
 }
 
@@ -2,11 +2,12 @@ use bevy::{
     math::*,
     prelude::*,
     render::camera::Camera,
+    render::pipeline::PrimitiveTopology,
     // render::mesh::VertexAttribute,
     // render::mesh::VertexAttributeValues,
     window::{CursorMoved, WindowId},
 };
-use meshie::Meshie;
+use meshie::MeshieBatch;
 use rays::Ray3d;
 
 use crate::main_3d_camera::CameraMarker;
@@ -59,16 +60,19 @@ fn start(
 ) {
     let ray_ball = Mesh::from(shape::Cube { size: 100. });
 
-    let mut cube_mesh = Mesh::from(shape::Quad {
+    // the batch keeps each quad's bounds, so picking never has to walk the vertices. the mesh
+    // goes into Assets, so any later edit has to go through the batch with the asset's mesh
+    let mut batch = MeshieBatch::new();
+    let mut cube_mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    let quad = Mesh::from(shape::Quad {
         flip: false,
         size: Vec2::new(1000., 1000.),
     });
-    let other = Mesh::from(shape::Quad {
-        flip: false,
-        size: Vec2::new(1000., 1000.),
-    });
-    let verts = cube_mesh.add_mesh(&other);
-    cube_mesh.translate_mesh(verts, Vec3::new(1200., 0.0, 0.0));
+    batch.add(&mut cube_mesh, &quad).expect("adding quad");
+    let other = batch.add(&mut cube_mesh, &quad).expect("adding quad");
+    batch
+        .translate(&mut cube_mesh, other, Vec3::new(1200., 0.0, 0.0))
+        .expect("moving quad");
 
     let cube_handle = meshes.add(cube_mesh);
     let ray_ball_handle = meshes.add(ray_ball);
@@ -83,6 +87,7 @@ fn start(
             ..Default::default()
         })
         .with(Selectable)
+        .with(batch)
         .with(InteractionDisplay {
             text: String::from("MESHIE!!!"),
         });
@@ -110,6 +115,7 @@ fn selecty(
     windows: Res<Windows>,
     mut query: Query<(
         &Handle<Mesh>,
+        &MeshieBatch,
         &mut InteractionDisplay,
         &Transform,
         &mut Selectable,
@@ -117,7 +123,16 @@ fn selecty(
     mut cam_query: Query<(&CameraMarker, &Camera, &Transform)>,
     mut ray_ball_query: Query<(&RayBall, &mut Transform)>,
 ) {
-    for (mesh_handle, mut interaction, transform, _) in &mut query.iter() {
+    for (mesh_handle, batch, mut interaction, transform, _) in &mut query.iter() {
+        // the cached bounds are only good while the asset is the mesh the batch built
+        match meshes.get(mesh_handle).map(|mesh| batch.check_mesh(mesh)) {
+            Some(Ok(())) => {}
+            Some(Err(error)) => {
+                println!("ship mesh changed outside its batch: {}", error);
+                continue;
+            }
+            None => continue,
+        }
         if click.pressed(MouseButton::Left) {
             for event in state.cursor_event_reader.iter(&mouse_pos) {
                 for (_, cam, ctran) in &mut cam_query.iter() {
@@ -135,23 +150,27 @@ fn selecty(
 
                     let pick_ray = Ray3d::new(ctran.translation(), ray_direction);
 
-                    let dist_from_cam_to_mesh =
-                        distance(&ctran.translation(), &transform.translation());
-
-                    let m_pos = ctran.translation()
-                        + (pick_ray.direction().normalize() * dist_from_cam_to_mesh);
-                    let dist_from_mouse_to_mesh_center = distance(&m_pos, &transform.translation());
-                    if dist_from_mouse_to_mesh_center < 500. {
-                        println!("{:#?}", obj_info_state.text);
-
-                        obj_info_state.text = interaction.text.clone();
-                    }
-
-                    for (_, mut ray_ball_trans) in &mut ray_ball_query.iter() {
-                        ray_ball_trans.set_translation(
-                            ctran.translation()
-                                + (pick_ray.direction().normalize() * dist_from_cam_to_mesh),
-                        );
+                    // the transform holds the entity's whole world matrix, scale and rotation
+                    // included
+                    let world = *transform.value();
+                    for sphere in batch
+                        .ids()
+                        .filter_map(|id| batch.bounding_sphere(id).ok())
+                        .map(|sphere| sphere.transformed(&world))
+                    {
+                        let dist_from_cam_to_mesh = distance(&ctran.translation(), &sphere.center);
+
+                        let m_pos = ctran.translation()
+                            + (pick_ray.direction().normalize() * dist_from_cam_to_mesh);
+                        if sphere.contains(m_pos) {
+                            println!("{:#?}", obj_info_state.text);
+
+                            obj_info_state.text = interaction.text.clone();
+                        }
+
+                        for (_, mut ray_ball_trans) in &mut ray_ball_query.iter() {
+                            ray_ball_trans.set_translation(m_pos);
+                        }
                     }
                 }
             }
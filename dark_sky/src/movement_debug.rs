@@ -221,14 +221,18 @@ fn update_meshie_momentum(
             .expect("I expected to get a debug mesh");
         let momentum: Ref<Momentum> = mom_query.get(debug.entity).expect("getting momentum");
         let transform: Ref<Transform> = tran_query.get(debug.entity).expect("getting transform");
-        debug_meshie.set_positions(debug.momentum, debug.momentum_pos.clone());
-        debug_meshie.rotate_from_meshie_center(
-            debug.momentum,
-            Quat::from_to_vec3(
-                transform.rotation().mul_vec3(Vec3::unit_y()),
-                momentum.inertia().extend(0.0),
-            ),
-        );
+        debug_meshie
+            .set_positions(debug.momentum, debug.momentum_pos.clone())
+            .expect("resetting momentum positions");
+        debug_meshie
+            .rotate_from_meshie_center(
+                debug.momentum,
+                Quat::from_to_vec3(
+                    transform.rotation().mul_vec3(Vec3::unit_y()),
+                    momentum.inertia().extend(0.0),
+                ),
+            )
+            .expect("rotating momentum");
         debug.prior_inertia = momentum.inertia().extend(0.0);
     }
 }
@@ -253,14 +257,12 @@ fn dotted_path(
             .expect("can't find meshie???");
         if (debug.last_path - transform.translation()).length() > 200.0 {
             debug.last_path = transform.translation();
-            let length = effects.availability.len();
-            for i in 0..length {
-                if effects.availability[i] == Availability::Open {
-                    effects_meshie
-                        .translate_mesh(effects.vertices[i as usize], transform.translation());
-                    effects.availability[i] = Availability::Used;
-                    break;
-                }
+            if let Some(slot) = effects.next_slot() {
+                // a recycled quad is still wherever it was last placed
+                let center = effects_meshie.get_center(slot).expect("effect center");
+                effects_meshie
+                    .translate_mesh(slot, transform.translation() - center)
+                    .expect("moving effect");
             }
         }
     }
@@ -43,8 +43,8 @@ fn sector_init(
         });
 
         // return the indicies added
-        let verts = mesh.add_mesh(&other);
-        mesh.translate_mesh(verts, Vec3::new(x, y, z));
+        let verts = mesh.add_mesh(&other).expect("adding sector");
+        mesh.translate_mesh(verts, Vec3::new(x, y, z)).expect("moving sector");
 
     }
 
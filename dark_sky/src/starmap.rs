@@ -6,7 +6,7 @@ use bevy::render::{
 
 use bevy::math::*;
 use material::StarMaterial;
-use meshie::generator::{DistributionFn, MeshBuilder, MeshConfig};
+use meshie::generator::MeshBuilder;
 
 use super::*;
 
@@ -75,47 +75,10 @@ fn start(
     let mat_handle = asset_server
         .load("../assets/STSCI-H-p1917b-q-5198x4801.png")
         .unwrap();
-    let mut mesh_builder = MeshBuilder {
-        texture_size: vec2(5198., 4807.),
-        config: vec![],
-    };
-    mesh_builder.config.push(MeshConfig {
-        count: 100000,
-        texture_position: bevy::sprite::Rect {
-            min: vec2(592., 863.),
-            max: vec2(601., 871.),
-        },
-        area: vec3(10000., 10000., 1000.),
-        distribution: DistributionFn::Random,
-    });
-    mesh_builder.config.push(MeshConfig {
-        count: 50000,
-        texture_position: bevy::sprite::Rect {
-            min: vec2(674., 857.),
-            max: vec2(685., 869.),
-        },
-        area: vec3(10000., 10000., 1000.),
-        distribution: DistributionFn::Random,
-    });
-    mesh_builder.config.push(MeshConfig {
-        count: 50000,
-        texture_position: bevy::sprite::Rect {
-            min: vec2(526., 854.),
-            max: vec2(543., 871.),
-        },
-        area: vec3(10000., 10000., 1000.),
-        distribution: DistributionFn::Random,
-    });
-    mesh_builder.config.push(MeshConfig {
-        count: 10000,
-        texture_position: bevy::sprite::Rect {
-            min: vec2(613., 880.),
-            max: vec2(656., 917.),
-        },
-        area: vec3(10000., 10000., 1000.),
-        distribution: DistributionFn::Random,
-    });
-    let mesh = mesh_builder.gen_mesh();
+    let mesh = MeshBuilder::load("assets/starmap.ron")
+        .and_then(|builder| builder.gen_mesh_parallel())
+        .map(|starfield| starfield.mesh)
+        .unwrap_or_else(|error| panic!("building assets/starmap.ron: {}", error));
     let mesh_handle = meshes.add(mesh);
     commands
         .spawn(MeshComponents {
@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use bevy::{math::*, prelude::*};
 use meshie::Meshie;
 
@@ -8,19 +10,26 @@ pub struct MovementDebug;
 #[derive(Default, Debug)]
 pub struct EffectsResource {
     pub mesh_handle: Handle<Mesh>,
-    pub availability: Vec<Availability>,
-    pub vertices: Vec<ds_range::Range>,
+    /// the effect quads not showing anything yet
+    pub slots: ds_range::RangeAllocator,
+    /// the quads in use, oldest first
+    pub placed: VecDeque<ds_range::Range>,
     pub chunk_size: u32,
     pub max_chunks: u32,
 }
-#[derive(Debug, PartialEq)]
-pub enum Availability {
-    Open,
-    Used,
-}
-impl Default for Availability {
-    fn default() -> Self {
-        Self::Open
+impl EffectsResource {
+    /// a quad for a new effect, `None` once they're all in use
+    pub fn next_slot(&mut self) -> Option<ds_range::Range> {
+        let slot = self.slots.alloc(self.chunk_size as usize)?;
+        self.placed.push_back(slot);
+        Some(slot)
+    }
+    /// hands the oldest effect's quad back, so the next `next_slot` can reuse it. returns the
+    /// quad, which still shows its effect until it's moved
+    pub fn recycle_oldest(&mut self) -> Option<ds_range::Range> {
+        let oldest = self.placed.pop_front()?;
+        self.slots.free(oldest).expect("freeing oldest effect");
+        Some(oldest)
     }
 }
 
@@ -39,15 +48,28 @@ pub fn generate_debug_meshie(entity: Entity, meshes: &mut ResMut<Assets<Mesh>>)
         size: vec2(10.0, 200.0),
         flip: false,
     });
-    meshie.translate_mesh(ds_range::Range { start: 0, end: 3 }, vec3(0.0, 190.0, 0.0));
-    meshie.set_uvs(ds_range::Range { start: 0, end: 3 }, vec![[0.5, 0.0], [0.5, 0.0], [0.5, 0.0], [0.5, 0.0]]);
-    let momentum = meshie.add_mesh(&Mesh::from(shape::Quad {
-        size: vec2(10.0, 200.0),
-        flip: false,
-    }));
-    meshie.set_uvs(momentum, vec![[0.0,0.5], [0.0,0.5], [0.0,0.5], [0.0,0.5]]);
-    meshie.translate_mesh(momentum, vec3(0.0, 290.0, 0.0));
-    let positions = meshie.get_positions(momentum);
+    meshie
+        .translate_mesh(ds_range::Range { start: 0, end: 4 }, vec3(0.0, 190.0, 0.0))
+        .expect("moving facing");
+    meshie
+        .set_uvs(
+            ds_range::Range { start: 0, end: 4 },
+            vec![[0.5, 0.0], [0.5, 0.0], [0.5, 0.0], [0.5, 0.0]],
+        )
+        .expect("setting facing uvs");
+    let momentum = meshie
+        .add_mesh(&Mesh::from(shape::Quad {
+            size: vec2(10.0, 200.0),
+            flip: false,
+        }))
+        .expect("adding momentum");
+    meshie
+        .set_uvs(momentum, vec![[0.0,0.5], [0.0,0.5], [0.0,0.5], [0.0,0.5]])
+        .expect("setting momentum uvs");
+    meshie
+        .translate_mesh(momentum, vec3(0.0, 290.0, 0.0))
+        .expect("moving momentum");
+    let positions = meshie.get_positions(momentum).expect("momentum positions");
 
     DebugMeshie {
         entity,
@@ -81,16 +103,17 @@ pub fn generate_effects_meshie(
         size: vec2(80.0, 80.0),
         flip: false,
     });
-    effects.vertices.push(ds_range::Range { start: 0, end: 3 });
-    effects.availability.push(Availability::Used);
-
+    // the first quad is only there to add to, the rest are handed out
+    let mut slots: Option<ds_range::Range> = None;
     for _ in 0..(max_chunks - 1) {
         let mesh = Mesh::from(shape::Quad {
             size: vec2(80.0, 80.0),
             flip: false,
         });
-        effects.vertices.push(effects_meshie.add_mesh(&mesh));
-        effects.availability.push(Availability::Open);
+        let added = effects_meshie.add_mesh(&mesh).expect("adding effect");
+        slots = Some(slots.map_or(added, |slots| slots + added));
     }
+    effects.slots = ds_range::RangeAllocator::new(slots.unwrap_or_default());
+    effects.placed.clear();
     effects_meshie
 }
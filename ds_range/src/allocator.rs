@@ -0,0 +1,217 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use crate::Range;
+
+/// Hands out ranges of a fixed space, like slots in a mesh that was built with room for
+/// more quads than it shows. Allocating takes the smallest free range that fits, and freed
+/// ranges are joined with free neighbours, both in O(log n).
+#[derive(Debug, Clone, Default)]
+pub struct RangeAllocator {
+    space: Range,
+    // free ranges start -> end, for finding the neighbours of a freed range
+    free_by_start: BTreeMap<usize, usize>,
+    // the same free ranges as (len, start), for finding the smallest that fits
+    free_by_len: BTreeSet<(usize, usize)>,
+    // handed out ranges start -> end
+    used: BTreeMap<usize, usize>,
+    free: usize,
+}
+
+/// How the allocator's space is split up right now.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct AllocatorStats {
+    pub used: usize,
+    pub free: usize,
+    pub free_ranges: usize,
+    pub largest_free: usize,
+}
+
+impl AllocatorStats {
+    /// 0 when all the free space is in one piece, near 1 when it's scattered in small pieces
+    pub fn fragmentation(&self) -> f32 {
+        if self.free == 0 {
+            0.0
+        } else {
+            1.0 - self.largest_free as f32 / self.free as f32
+        }
+    }
+}
+
+/// `free` was given a range that isn't one `alloc` handed out.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NotAllocated(pub Range);
+
+impl fmt::Display for NotAllocated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} wasn't allocated", self.0)
+    }
+}
+
+impl std::error::Error for NotAllocated {}
+
+impl RangeAllocator {
+    /// an allocator with all of `space` free
+    pub fn new(space: Range) -> Self {
+        let mut allocator = Self {
+            space,
+            ..Self::default()
+        };
+        allocator.release(space);
+        allocator
+    }
+    /// the whole space, free and used
+    pub fn space(&self) -> Range {
+        self.space
+    }
+    /// `len` contiguous indices, or `None` if no free range is that long
+    pub fn alloc(&mut self, len: usize) -> Option<Range> {
+        assert!(len > 0, "allocating an empty range");
+        let (free_len, start) = *self.free_by_len.range((len, 0)..).next()?;
+        self.remove_free(start, start + free_len);
+        if free_len > len {
+            self.insert_free(start + len, start + free_len);
+        }
+        self.used.insert(start, start + len);
+        Some(Range {
+            start,
+            end: start + len,
+        })
+    }
+    /// gives back a range from `alloc`, joining it with any free range either side
+    pub fn free(&mut self, range: Range) -> Result<(), NotAllocated> {
+        if self.used.get(&range.start) != Some(&range.end) {
+            return Err(NotAllocated(range));
+        }
+        self.used.remove(&range.start);
+        self.release(range);
+        Ok(())
+    }
+    /// adds `len` free indices to the end of the space, for when the mesh grows. returns the
+    /// new indices
+    pub fn grow(&mut self, len: usize) -> Range {
+        let added = Range {
+            start: self.space.end,
+            end: self.space.end + len,
+        };
+        self.space.end = added.end;
+        self.release(added);
+        added
+    }
+    pub fn stats(&self) -> AllocatorStats {
+        AllocatorStats {
+            used: self.space.len() - self.free,
+            free: self.free,
+            free_ranges: self.free_by_start.len(),
+            largest_free: self
+                .free_by_len
+                .iter()
+                .next_back()
+                .map_or(0, |(len, _)| *len),
+        }
+    }
+
+    fn release(&mut self, range: Range) {
+        if range.is_empty() {
+            return;
+        }
+        let (mut start, mut end) = (range.start, range.end);
+        let before = self
+            .free_by_start
+            .range(..start)
+            .next_back()
+            .map(|(s, e)| (*s, *e));
+        if let Some((before_start, before_end)) = before {
+            if before_end == start {
+                self.remove_free(before_start, before_end);
+                start = before_start;
+            }
+        }
+        if let Some(&after_end) = self.free_by_start.get(&end) {
+            self.remove_free(end, after_end);
+            end = after_end;
+        }
+        self.insert_free(start, end);
+    }
+    fn insert_free(&mut self, start: usize, end: usize) {
+        self.free_by_start.insert(start, end);
+        self.free_by_len.insert((end - start, start));
+        self.free += end - start;
+    }
+    fn remove_free(&mut self, start: usize, end: usize) {
+        self.free_by_start.remove(&start);
+        self.free_by_len.remove(&(end - start, start));
+        self.free -= end - start;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RangeSet;
+    use proptest::prelude::*;
+
+    #[test]
+    fn freed_neighbours_are_joined() {
+        let mut allocator = RangeAllocator::new(Range::from(4..20));
+        let a = allocator.alloc(4).unwrap();
+        let b = allocator.alloc(4).unwrap();
+        let c = allocator.alloc(4).unwrap();
+        assert_eq!(
+            (a, b, c),
+            (Range::from(4..8), Range::from(8..12), Range::from(12..16))
+        );
+        assert_eq!(allocator.alloc(8), None);
+
+        allocator.free(a).unwrap();
+        allocator.free(c).unwrap();
+        let stats = allocator.stats();
+        assert_eq!(stats.free_ranges, 2);
+        assert_eq!(stats.largest_free, 8);
+        assert_eq!(stats.fragmentation(), 1.0 - 8.0 / 12.0);
+        assert_eq!(allocator.free(c), Err(NotAllocated(c)));
+
+        // the smallest gap that fits is used first
+        assert_eq!(allocator.alloc(2), Some(Range::from(4..6)));
+        allocator.free(b).unwrap();
+        assert_eq!(allocator.alloc(14), Some(Range::from(6..20)));
+        assert_eq!(allocator.grow(4), Range::from(20..24));
+        assert_eq!(allocator.alloc(4), Some(Range::from(20..24)));
+    }
+
+    proptest! {
+        #[test]
+        fn allocations_never_overlap(ops in prop::collection::vec((1usize..12, any::<bool>()), 0..80)) {
+            let space = Range::from(0..100);
+            let mut allocator = RangeAllocator::new(space);
+            let mut held: Vec<Range> = Vec::new();
+            for (len, free) in ops {
+                if free && !held.is_empty() {
+                    let range = held.swap_remove(len % held.len());
+                    prop_assert!(allocator.free(range).is_ok());
+                    prop_assert!(allocator.free(range).is_err());
+                } else if let Some(range) = allocator.alloc(len) {
+                    prop_assert_eq!(range.len(), len);
+                    prop_assert!(range.start >= space.start && range.end <= space.end);
+                    prop_assert!(held.iter().all(|other| !other.overlaps(range)));
+                    held.push(range);
+                } else {
+                    prop_assert!(allocator.stats().largest_free < len);
+                }
+                let stats = allocator.stats();
+                let used: usize = held.iter().map(|range| range.len()).sum();
+                prop_assert_eq!(stats.used, used);
+                prop_assert_eq!(stats.free + stats.used, space.len());
+                // free ranges are always joined, so they're exactly the gaps between held ones
+                let gaps = RangeSet::from(space).difference(&held.iter().copied().collect());
+                prop_assert_eq!(stats.free_ranges, gaps.ranges().len());
+            }
+            for range in held {
+                allocator.free(range).unwrap();
+            }
+            prop_assert_eq!(allocator.stats().largest_free, space.len());
+        }
+    }
+}
@@ -1,3 +1,8 @@
+mod allocator;
+mod range_set;
+pub use allocator::{AllocatorStats, NotAllocated, RangeAllocator};
+pub use range_set::RangeSet;
+
 #[derive(Debug, Copy, Clone, Default, Ord, PartialOrd, Eq, PartialEq)]
 pub struct Range {
     pub start: usize,
@@ -28,8 +33,61 @@ impl Range {
             end: self.end,
         }
     }
+    /// 0 for reversed ranges, which are empty like `is_empty` says
     pub fn len(&self) -> usize {
-        self.end - self.start
+        self.end.saturating_sub(self.start)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+    pub fn contains(&self, index: usize) -> bool {
+        self.start <= index && index < self.end
+    }
+    /// true if the two share at least one index, unlike `+` which also joins ranges with a gap
+    /// between them
+    pub fn overlaps(&self, other: Range) -> bool {
+        !self.is_empty() && !other.is_empty() && self.start < other.end && other.start < self.end
+    }
+    /// the indices in both, `None` if they don't overlap
+    pub fn intersection(&self, other: Range) -> Option<Range> {
+        if self.overlaps(other) {
+            Some(Range {
+                start: self.start.max(other.start),
+                end: self.end.min(other.end),
+            })
+        } else {
+            None
+        }
+    }
+    /// `start..index` and `index..end`, with `index` clamped into the range
+    pub fn split_at(&self, index: usize) -> (Range, Range) {
+        let index = index.max(self.start).min(self.end);
+        (
+            Range {
+                start: self.start,
+                end: index,
+            },
+            Range {
+                start: index,
+                end: self.end,
+            },
+        )
+    }
+    /// the range moved by `offset`, panics if it would move below zero
+    pub fn shift(&self, offset: isize) -> Range {
+        let shift = |index: usize| {
+            if offset < 0 {
+                index
+                    .checked_sub(offset.wrapping_neg() as usize)
+                    .expect("range shifted below zero")
+            } else {
+                index + offset as usize
+            }
+        };
+        Range {
+            start: shift(self.start),
+            end: shift(self.end),
+        }
     }
 }
 
@@ -53,6 +111,9 @@ impl From<Range> for std::ops::Range<usize> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+
     #[test]
     fn it_works() {
         let s = Range { start: 10, end: 20 };
@@ -63,7 +124,56 @@ mod tests {
         let x = s + o;
         for i in o.iter() {}
         for i in o.iter() {}
-    
+
         // println!("{:?} {:?} {:?} {:?} {:?}", x , y , z , s , o);
     }
+
+    #[test]
+    fn set_operations() {
+        let a = Range::from(10..20);
+        let b = Range::from(15..30);
+        assert_eq!(a.intersection(b), Some(Range::from(15..20)));
+        assert!(a.overlaps(b));
+        // touching isn't overlapping
+        assert_eq!(a.intersection(Range::from(20..25)), None);
+        assert!(!a.overlaps(Range::from(12..12)));
+        assert!(a.contains(10) && a.contains(19) && !a.contains(20));
+        assert!(Range::from(3..3).is_empty());
+        assert_eq!(Range { start: 5, end: 3 }.len(), 0);
+        assert_eq!(a.split_at(14), (Range::from(10..14), Range::from(14..20)));
+        assert_eq!(a.split_at(40), (a, Range::from(20..20)));
+        assert_eq!(a.shift(-10), Range::from(0..10));
+        assert_eq!(a.shift(5), Range::from(15..25));
+    }
+
+    #[test]
+    #[should_panic(expected = "range shifted below zero")]
+    fn shift_below_zero_panics() {
+        Range::from(2..4).shift(-3);
+    }
+
+    proptest! {
+        #[test]
+        fn set_operations_match_a_btreeset(
+            // ends are free to come before starts, reversed ranges are empty
+            (a, b) in (0usize..40, 0usize..40, 0usize..40, 0usize..40)
+                .prop_map(|(s, e, t, f)| (Range::from(s..e), Range::from(t..f))),
+            index in 0usize..50,
+        ) {
+            let model = |r: Range| r.iter().collect::<BTreeSet<_>>();
+            let both: BTreeSet<_> = model(a).intersection(&model(b)).copied().collect();
+            prop_assert_eq!(a.intersection(b).map_or_else(BTreeSet::new, model), both.clone());
+            prop_assert_eq!(a.overlaps(b), !both.is_empty());
+            prop_assert_eq!(a.contains(index), model(a).contains(&index));
+            prop_assert_eq!(a.is_empty(), model(a).is_empty());
+            prop_assert_eq!(a.len(), model(a).len());
+            let (low, high) = a.split_at(index);
+            prop_assert_eq!(low.len() + high.len(), a.len());
+            prop_assert!(low.iter().all(|i| i < index) && high.iter().all(|i| i >= index));
+            prop_assert_eq!(
+                model(a.shift(index as isize)),
+                model(a).iter().map(|i| i + index).collect::<BTreeSet<_>>()
+            );
+        }
+    }
 }
@@ -0,0 +1,199 @@
+use std::{cmp::Ordering, iter::FromIterator};
+
+use crate::Range;
+
+/// A set of indices kept as sorted, disjoint ranges. Ranges that overlap or touch are joined
+/// as they go in, so the same indices always give the same ranges.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RangeSet {
+    ranges: Vec<Range>,
+}
+
+impl RangeSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// the ranges in order, none empty and none touching
+    pub fn ranges(&self) -> &[Range] {
+        &self.ranges
+    }
+    /// every index in the set, in order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.ranges.iter().flat_map(|range| range.iter())
+    }
+    /// how many indices are in the set
+    pub fn len(&self) -> usize {
+        self.ranges.iter().map(|range| range.len()).sum()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+    pub fn contains(&self, index: usize) -> bool {
+        let i = self.first_where(|range| range.end > index);
+        self.ranges
+            .get(i)
+            .is_some_and(|range| range.contains(index))
+    }
+    /// adds the indices of `range`, joining it with any ranges it overlaps or touches
+    pub fn insert(&mut self, range: Range) {
+        if range.is_empty() {
+            return;
+        }
+        let first = self.first_where(|r| r.end >= range.start);
+        let last = self.first_where(|r| r.start > range.end);
+        let joined = if first < last {
+            Range {
+                start: range.start.min(self.ranges[first].start),
+                end: range.end.max(self.ranges[last - 1].end),
+            }
+        } else {
+            range
+        };
+        self.ranges.splice(first..last, Some(joined));
+    }
+    /// takes the indices of `range` out, splitting a range if `range` falls inside it
+    pub fn remove(&mut self, range: Range) {
+        if range.is_empty() {
+            return;
+        }
+        let first = self.first_where(|r| r.end > range.start);
+        let last = self.first_where(|r| r.start >= range.end);
+        if first == last {
+            return;
+        }
+        let before = Range {
+            start: self.ranges[first].start,
+            end: range.start,
+        };
+        let after = Range {
+            start: range.end,
+            end: self.ranges[last - 1].end,
+        };
+        let kept = [before, after];
+        self.ranges
+            .splice(first..last, kept.iter().copied().filter(|r| !r.is_empty()));
+    }
+    /// the indices in either set
+    pub fn union(&self, other: &RangeSet) -> RangeSet {
+        let mut set = self.clone();
+        for range in &other.ranges {
+            set.insert(*range);
+        }
+        set
+    }
+    /// the indices in `self` but not in `other`
+    pub fn difference(&self, other: &RangeSet) -> RangeSet {
+        let mut set = self.clone();
+        for range in &other.ranges {
+            set.remove(*range);
+        }
+        set
+    }
+
+    // the index of the first range that passes `test`, the ranges must fail it then pass it
+    fn first_where(&self, test: impl Fn(&Range) -> bool) -> usize {
+        self.ranges
+            .binary_search_by(|range| {
+                if test(range) {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                }
+            })
+            .unwrap_err()
+    }
+}
+
+impl From<Range> for RangeSet {
+    fn from(range: Range) -> Self {
+        let mut set = Self::new();
+        set.insert(range);
+        set
+    }
+}
+
+impl FromIterator<Range> for RangeSet {
+    fn from_iter<I: IntoIterator<Item = Range>>(ranges: I) -> Self {
+        let mut set = Self::new();
+        for range in ranges {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::BTreeSet;
+
+    fn ranges() -> impl Strategy<Value = Vec<Range>> {
+        prop::collection::vec(
+            (0usize..64, 0usize..16).prop_map(|(start, len)| Range::from(start..start + len)),
+            0..12,
+        )
+    }
+
+    fn model(ranges: &[Range]) -> BTreeSet<usize> {
+        ranges.iter().flat_map(|range| range.iter()).collect()
+    }
+
+    fn assert_canonical(set: &RangeSet) {
+        for range in set.ranges() {
+            assert!(!range.is_empty(), "{:?}", set);
+        }
+        for pair in set.ranges().windows(2) {
+            // sorted with a gap between, otherwise they should have been joined
+            assert!(pair[0].end < pair[1].start, "{:?}", set);
+        }
+    }
+
+    #[test]
+    fn touching_ranges_are_joined() {
+        let mut set: RangeSet = vec![Range::from(0..4), Range::from(8..10)]
+            .into_iter()
+            .collect();
+        set.insert(Range::from(4..8));
+        assert_eq!(set.ranges(), &[Range::from(0..10)]);
+        set.remove(Range::from(2..3));
+        assert_eq!(set.ranges(), &[Range::from(0..2), Range::from(3..10)]);
+        assert_eq!(set.len(), 9);
+        assert!(!set.contains(2) && set.contains(3) && !set.contains(10));
+    }
+
+    proptest! {
+        #[test]
+        fn inserts_match_a_btreeset(ranges in ranges()) {
+            let set: RangeSet = ranges.iter().copied().collect();
+            assert_canonical(&set);
+            let expected = model(&ranges);
+            prop_assert_eq!(set.iter().collect::<BTreeSet<_>>(), expected.clone());
+            prop_assert_eq!(set.len(), expected.len());
+            for index in 0..90 {
+                prop_assert_eq!(set.contains(index), expected.contains(&index));
+            }
+        }
+
+        #[test]
+        fn union_and_difference_match_a_btreeset(a in ranges(), b in ranges()) {
+            let (set_a, set_b): (RangeSet, RangeSet) =
+                (a.iter().copied().collect(), b.iter().copied().collect());
+            let (model_a, model_b) = (model(&a), model(&b));
+
+            let union = set_a.union(&set_b);
+            assert_canonical(&union);
+            prop_assert_eq!(
+                union.iter().collect::<BTreeSet<_>>(),
+                model_a.union(&model_b).copied().collect::<BTreeSet<_>>()
+            );
+
+            let difference = set_a.difference(&set_b);
+            assert_canonical(&difference);
+            prop_assert_eq!(
+                difference.iter().collect::<BTreeSet<_>>(),
+                model_a.difference(&model_b).copied().collect::<BTreeSet<_>>()
+            );
+        }
+    }
+}
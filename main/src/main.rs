@@ -16,7 +16,7 @@ use camera::{camera_movement, update_camera_distance, CameraMarker, MouseState};
 use material::{GlobalMaterial, MeshMaterial, StarMaterial};
 use mega_mesh::plugin::MegaMeshPlugin;
 use mesh::{EditableMesh, MeshMaker};
-use meshie::generator::{DistributionFn, MeshBuilder, MeshConfig};
+use meshie::{generator::MeshBuilder, Splitter};
 use node_graph::{Graph, Ship};
 use shape::Quad;
 // use shapes::Skybox;
@@ -208,11 +208,10 @@ fn setup(
     for seed in 1..2 {
         let graph = Graph::new(10, 60, 60, seed);
         println!("nodes: {}", graph.nodes.len());
-        let mut meshmakers = Vec::new();
-        let mut m_maker = MeshMaker::new();
-        let mut count = 0;
+        let mut splitter = Splitter::new(64000);
 
         for node in &graph.nodes {
+            let mut m_maker = MeshMaker::new();
             let rect = atlas_info.textures[node.texture as usize].rect;
             let quad = Mesh::from(shape::Quad {
                 size: vec2(rect.max[0] - rect.min[0], rect.max[1] - rect.min[1]),
@@ -250,29 +249,23 @@ fn setup(
             match quad.indices.unwrap() {
                 bevy::render::mesh::Indices::U16(_) => {}
                 bevy::render::mesh::Indices::U32(i) => {
-                    for ind in i {
-                        m_maker.indices.push(ind + count as u32);
-                    }
+                    m_maker.indices.extend(i);
                 }
             }
 
+            let placement = splitter
+                .push(&m_maker.generate_mesh())
+                .expect("packing node quad");
             commands.spawn((Ship {
-                vert_indices: count..(count + 4),
+                vert_indices: placement.vertices.start as u32..placement.vertices.end as u32,
                 texture_index: 1.0,
             },));
-
-            count += 4;
-            if count >= 64000 {
-                meshmakers.push(m_maker);
-                m_maker = MeshMaker::new();
-                count = 0;
-            }
         }
-        meshmakers.push(m_maker);
-        println!("meshmakers: {}", meshmakers.len());
+        let (chunks, _) = splitter.finish();
+        println!("chunks: {}", chunks.len());
 
-        for meshmaker in &meshmakers {
-            let mesh_handle = meshes.add(meshmaker.generate_mesh());
+        for chunk in chunks {
+            let mesh_handle = meshes.add(chunk);
             handle_res.mesh_handle = mesh_handle;
             commands
                 .spawn(MeshComponents {
@@ -333,47 +326,10 @@ fn setup(
     let mat_handle = asset_server
         .load("../assets/STSCI-H-p1917b-q-5198x4801.png")
         .unwrap();
-    let mut mesh_builder = MeshBuilder {
-        texture_size: vec2(5198., 4807.),
-        config: vec![],
-    };
-    mesh_builder.config.push(MeshConfig {
-        count: 1000000,
-        texture_position: bevy::sprite::Rect {
-            min: vec2(592., 863.),
-            max: vec2(601., 871.),
-        },
-        area: vec3(100000., 100000., 1000.),
-        distribution: DistributionFn::Random,
-    });
-    mesh_builder.config.push(MeshConfig {
-        count: 500000,
-        texture_position: bevy::sprite::Rect {
-            min: vec2(674., 857.),
-            max: vec2(685., 869.),
-        },
-        area: vec3(100000., 100000., 1000.),
-        distribution: DistributionFn::Random,
-    });
-    mesh_builder.config.push(MeshConfig {
-        count: 500000,
-        texture_position: bevy::sprite::Rect {
-            min: vec2(526., 854.),
-            max: vec2(543., 871.),
-        },
-        area: vec3(100000., 100000., 1000.),
-        distribution: DistributionFn::Random,
-    });
-    mesh_builder.config.push(MeshConfig {
-        count: 100000,
-        texture_position: bevy::sprite::Rect {
-            min: vec2(613., 880.),
-            max: vec2(656., 917.),
-        },
-        area: vec3(100000., 100000., 1000.),
-        distribution: DistributionFn::Random,
-    });
-    let mesh = mesh_builder.gen_mesh();
+    let mesh = MeshBuilder::load("assets/editor_starfield.ron")
+        .and_then(|builder| builder.gen_mesh_parallel())
+        .map(|starfield| starfield.mesh)
+        .unwrap_or_else(|error| panic!("building assets/editor_starfield.ron: {}", error));
     // let mut rng = StdRng::from_entropy();
     let mesh_handle = meshes.add(mesh);
     commands
@@ -404,14 +360,11 @@ fn aspect_ratio(mut meshes: ResMut<Assets<Mesh>>, handle: Res<MeshHandles>, wind
             let right = width;
             let bottom = -height;
             let top = height;
-            match mesh.attributes[0].values {
-                bevy::render::mesh::VertexAttributeValues::Float3(ref mut vertices) => {
-                    vertices[2] = [left, top, 0.0];
-                    vertices[3] = [right, top, 0.0];
-                    vertices[1] = [left, bottom, 0.0];
-                    vertices[0] = [right, bottom, 0.0];
-                }
-                _ => (),
+            if let Some(vertices) = mesh.get_mut_vertex_positions() {
+                vertices[2] = [left, top, 0.0];
+                vertices[3] = [right, top, 0.0];
+                vertices[1] = [left, bottom, 0.0];
+                vertices[0] = [right, bottom, 0.0];
             }
         }
     }
@@ -1,10 +1,13 @@
 use bevy::{prelude::Mesh, render::mesh::Indices, render::mesh::VertexAttribute, render::pipeline::PrimitiveTopology};
+use meshie::attributes;
 
 pub struct MeshMaker {
     pub vert_pos: Vec<[f32; 3]>,
     pub vert_norm: Vec<[f32; 3]>,
     pub vert_uvs: Vec<[f32; 2]>,
-    // pub vert_colors: Vec<[f32; 4]>,
+    /// the forward and star shaders read a color for every vertex, vertices past the end of
+    /// this are white
+    pub vert_colors: Vec<[f32; 4]>,
     // pub vert_textures: Vec<f32>,
     pub indices: Vec<u32>,
 }
@@ -14,7 +17,7 @@ impl MeshMaker {
             vert_pos: Vec::new(),
             vert_norm: Vec::new(),
             vert_uvs: Vec::new(),
-            // vert_colors: Vec::new(),
+            vert_colors: Vec::new(),
             // vert_textures: Vec::new(),
             indices: Vec::new(),
         }
@@ -28,8 +31,9 @@ impl MeshMaker {
             .push(VertexAttribute::normal(self.vert_norm.clone()));
         mesh.attributes
             .push(VertexAttribute::uv(self.vert_uvs.clone()));
-        // mesh.attributes
-        //     .push(VertexAttribute::color(self.vert_colors.clone()));
+        let mut colors = self.vert_colors.clone();
+        colors.resize(self.vert_pos.len(), attributes::WHITE);
+        mesh.attributes.push(attributes::color(colors));
         // mesh.attributes
         //     .push(VertexAttribute::texture(self.vert_textures.clone()));
         mesh
@@ -40,7 +44,7 @@ pub trait EditableMesh {
     fn get_vertex_positions(&self) -> Option<Vec<[f32; 3]>>;
     fn get_vertex_normals(&self) -> Option<Vec<[f32; 3]>>;
     fn get_vertex_uvs(&self) -> Option<Vec<[f32; 2]>>;
-    // fn get_vertex_colors(&self) -> Option<Vec<[f32; 4]>>;
+    fn get_vertex_colors(&self) -> Option<Vec<[f32; 4]>>;
     // fn get_vertex_textures(&self) -> Option<Vec<f32>>;
     // fn get_vertices(&self) -> Option<Vec<Vertex>>;
     fn get_mut_vertex_positions(&mut self) -> Option<&mut Vec<[f32; 3]>>;
@@ -51,77 +55,41 @@ pub trait EditableMesh {
 
 impl EditableMesh for Mesh {
     fn get_vertex_positions(&self) -> Option<Vec<[f32; 3]>> {
-        match self.attributes[0].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref vertices) => {
-                Some(vertices.clone())
-            }
-            _ => None,
-        }
+        attributes::positions(self).ok().cloned()
     }
     fn get_vertex_normals(&self) -> Option<Vec<[f32; 3]>> {
-        match self.attributes[1].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref vertices) => {
-                Some(vertices.clone())
-            }
-            _ => None,
-        }
+        attributes::normals(self).ok().cloned()
     }
     fn get_vertex_uvs(&self) -> Option<Vec<[f32; 2]>> {
-        match self.attributes[2].values {
-            bevy::render::mesh::VertexAttributeValues::Float2(ref vertices) => {
-                Some(vertices.clone())
-            }
-            _ => None,
-        }
+        attributes::uvs(self).ok().cloned()
+    }
+    fn get_vertex_colors(&self) -> Option<Vec<[f32; 4]>> {
+        attributes::colors(self).ok().cloned()
     }
-    // fn get_vertex_colors(&self) -> Option<Vec<[f32; 4]>> {
-    //     match self.attributes[3].values {
-    //         bevy::render::mesh::VertexAttributeValues::Float4(ref vertices) => {
-    //             Some(vertices.clone())
-    //         }
-    //         _ => None,
-    //     }
-    // }
     // fn get_vertex_textures(&self) -> Option<Vec<f32>> {
-    //     match self.attributes[4].values {
-    //         bevy::render::mesh::VertexAttributeValues::Float(ref vertices) => {
+    //     match attributes::attribute(self, "Vertex_Texture") {
+    //         Ok(VertexAttributeValues::Float(vertices)) => {
     //             Some(vertices.clone())
     //         }
     //         _ => None,
     //     }
     // }
     fn get_mut_vertex_positions(&mut self) -> Option<&mut Vec<[f32; 3]>> {
-        match self.attributes[0].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref mut vertices) => Some(vertices),
-            _ => None,
-        }
+        attributes::positions_mut(self).ok()
     }
     fn get_mut_vertex_normals(&mut self) -> Option<&mut Vec<[f32; 3]>> {
-        match self.attributes[1].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref mut vertices) => Some(vertices),
-            _ => None,
-        }
+        attributes::normals_mut(self).ok()
     }
     fn get_mut_vertex_uvs(&mut self) -> Option<&mut Vec<[f32; 2]>> {
-        match self.attributes[2].values {
-            bevy::render::mesh::VertexAttributeValues::Float2(ref mut vertices) => Some(vertices),
-            _ => None,
-        }
+        attributes::uvs_mut(self).ok()
     }
 }
 
 // pub trait NewVertexAttributes {
-//     fn color(colors: Vec<[f32; 4]>) -> VertexAttribute;
 //     fn texture(textures: Vec<f32>) -> VertexAttribute;
 // }
 
 // impl NewVertexAttributes for VertexAttribute {
-//     fn color(colors: Vec<[f32; 4]>) -> VertexAttribute {
-//         VertexAttribute {
-//             name: "Vertex_Color".into(),
-//             values: VertexAttributeValues::Float4(colors),
-//         }
-//     }
 //     fn texture(textures: Vec<f32>) -> VertexAttribute {
 //         VertexAttribute {
 //             name: "Vertex_Texture".into(),
@@ -4,12 +4,13 @@ use bevy::{
 };
 use noise::utils::{NoiseMapBuilder, PlaneMapBuilder};
 use noise::Seedable;
+use meshie::{attributes, Splitter};
 use rand::Rng;
 
 pub fn generate_mega_mesh(
     width: i32,
     height: i32,
-) -> Vec<MeshMaker> {
+) -> Vec<Mesh> {
     let mut rng = rand::thread_rng();
     let mut stars = Vec::new();
 
@@ -29,11 +30,10 @@ pub fn generate_mega_mesh(
     }
     println!("stars count: {:?}", stars.len());
 
-    let mut meshmakers = Vec::new();
-    let mut m_maker = MeshMaker::new();
-    let mut count = 0;
+    let mut splitter = Splitter::new(64000);
 
     for star in stars {
+        let mut m_maker = MeshMaker::new();
         // let z_value = rng.gen_range(1000.0, 1500.0);
         let z_value = 1500.;
 
@@ -68,24 +68,19 @@ pub fn generate_mega_mesh(
         match quad.indices.unwrap() {
             bevy::render::mesh::Indices::U16(_) => {}
             bevy::render::mesh::Indices::U32(i) => {
-                for ind in i {
-                    m_maker.indices.push(ind + count as u32);
-                }
+                m_maker.indices.extend(i);
             }
         }
-        count += 4;
-        if count >= 64000 {
-            meshmakers.push(m_maker);
-            m_maker = MeshMaker::new();
-            count = 0;
-        }
+        splitter
+            .push(&m_maker.generate_mesh())
+            .expect("packing star quad");
     }
-    meshmakers.push(m_maker);
+    let (chunks, placements) = splitter.finish();
 
-    println!("vertex count: {:?}", count);
-    // println!("meshes: {:?}", meshmakers);
+    println!("vertex count: {:?}", placements.len() * 4);
+    // println!("meshes: {:?}", chunks);
 
-    meshmakers
+    chunks
 }
 
 #[derive(Debug)]
@@ -129,45 +124,21 @@ trait EditableMesh {
 
 impl EditableMesh for Mesh {
     fn get_vertex_positions(&self) -> Option<Vec<[f32; 3]>> {
-        match self.attributes[0].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref vertices) => {
-                Some(vertices.clone())
-            }
-            _ => None,
-        }
+        attributes::positions(self).ok().cloned()
     }
     fn get_vertex_normals(&self) -> Option<Vec<[f32; 3]>> {
-        match self.attributes[1].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref vertices) => {
-                Some(vertices.clone())
-            }
-            _ => None,
-        }
+        attributes::normals(self).ok().cloned()
     }
     fn get_vertex_uvs(&self) -> Option<Vec<[f32; 2]>> {
-        match self.attributes[2].values {
-            bevy::render::mesh::VertexAttributeValues::Float2(ref vertices) => {
-                Some(vertices.clone())
-            }
-            _ => None,
-        }
+        attributes::uvs(self).ok().cloned()
     }
     fn get_mut_vertex_positions(&mut self) -> Option<&mut Vec<[f32; 3]>> {
-        match self.attributes[0].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref mut vertices) => Some(vertices),
-            _ => None,
-        }
+        attributes::positions_mut(self).ok()
     }
     fn get_mut_vertex_normals(&mut self) -> Option<&mut Vec<[f32; 3]>> {
-        match self.attributes[1].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref mut vertices) => Some(vertices),
-            _ => None,
-        }
+        attributes::normals_mut(self).ok()
     }
     fn get_mut_vertex_uvs(&mut self) -> Option<&mut Vec<[f32; 2]>> {
-        match self.attributes[2].values {
-            bevy::render::mesh::VertexAttributeValues::Float2(ref mut vertices) => Some(vertices),
-            _ => None,
-        }
+        attributes::uvs_mut(self).ok()
     }
 }
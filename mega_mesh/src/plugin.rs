@@ -140,11 +140,11 @@ fn mega_mesh_startup(
     //     })));
 
     // GENERATE MESH
-    let meshmakers = generate_mega_mesh(10, 10);
-    println!("mesh count: {:?}", meshmakers.len());
+    let chunks = generate_mega_mesh(10, 10);
+    println!("mesh count: {:?}", chunks.len());
 
-    for m in meshmakers {
-        megamesh.mesh_handle.push(meshes.add(m.generate_mesh()));
+    for chunk in chunks {
+        megamesh.mesh_handle.push(meshes.add(chunk));
     }
 
     // SPAWN IT
@@ -0,0 +1,90 @@
+use bevy::{
+    math::{vec2, vec3},
+    sprite::Rect,
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+use meshie::{
+    atlas::Atlas,
+    generator::{DistributionFn, MeshBuilder, MeshConfig, Variation},
+};
+
+// four layers of stars like the starmap, about 100k quads in all
+fn starfield() -> MeshBuilder {
+    let layer = |count, sprite: &str, distribution| MeshConfig {
+        count,
+        sprite: sprite.to_string(),
+        area: vec3(10000., 10000., 1000.),
+        distribution,
+        seed: None,
+        variation: Variation {
+            scale: (0.5, 1.5),
+            rotate: true,
+            brightness: (0.6, 1.0),
+            color_jitter: 0.05,
+        },
+    };
+    MeshBuilder {
+        atlas: Atlas::new(vec2(64., 64.))
+            .with_sprite(
+                "small",
+                Rect {
+                    min: vec2(0., 0.),
+                    max: vec2(16., 16.),
+                },
+            )
+            .with_sprite(
+                "large",
+                Rect {
+                    min: vec2(16., 16.),
+                    max: vec2(64., 64.),
+                },
+            ),
+        seed: 0,
+        config: vec![
+            layer(50000, "small", DistributionFn::Random),
+            layer(25000, "small", DistributionFn::Gaussian { sigma: 0.3 }),
+            layer(
+                20000,
+                "large",
+                DistributionFn::Spiral {
+                    arms: 3,
+                    turns: 1.0,
+                    spread: 0.05,
+                },
+            ),
+            layer(5000, "large", DistributionFn::Grid { jitter: 0.4 }),
+        ],
+    }
+}
+
+// the sizes the game ships with, about 2.1M quads
+fn editor_starfield() -> MeshBuilder {
+    MeshBuilder::load(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../assets/editor_starfield.ron"
+    ))
+    .unwrap()
+}
+
+// quad_by_quad is how the generator used to work, the baseline the others are measured against
+fn bench(c: &mut Criterion, name: &str, builder: &MeshBuilder) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+    group.bench_function("quad_by_quad", |b| {
+        b.iter(|| builder.gen_mesh_quad_by_quad().unwrap())
+    });
+    group.bench_function("gen_mesh", |b| b.iter(|| builder.gen_mesh().unwrap()));
+    #[cfg(feature = "parallel")]
+    group.bench_function("gen_mesh_parallel", |b| {
+        b.iter(|| builder.gen_mesh_parallel().unwrap())
+    });
+    group.finish();
+}
+
+fn generator(c: &mut Criterion) {
+    bench(c, "starfield", &starfield());
+    bench(c, "editor_starfield", &editor_starfield());
+}
+
+criterion_group!(benches, generator);
+criterion_main!(benches);
@@ -0,0 +1,66 @@
+use bevy::math::{vec2, vec3, Mat4};
+use meshie::{
+    atlas::Atlas,
+    generator::{DistributionFn, MeshBuilder, MeshConfig, Variation},
+    gltf::{save_gltf, GltfMesh},
+};
+
+// writes a small starfield to starfield.gltf so it can be looked at in blender
+fn main() {
+    let mesh_builder = MeshBuilder {
+        atlas: Atlas::new(vec2(5198., 4807.))
+            .with_sprite(
+                "star_small",
+                bevy::sprite::Rect {
+                    min: vec2(592., 863.),
+                    max: vec2(601., 871.),
+                },
+            )
+            .with_sprite(
+                "star_flare",
+                bevy::sprite::Rect {
+                    min: vec2(613., 880.),
+                    max: vec2(656., 917.),
+                },
+            ),
+        seed: 0,
+        config: vec![
+            MeshConfig {
+                count: 100,
+                sprite: "star_small".to_string(),
+                area: vec3(1000., 1000., 10.),
+                distribution: DistributionFn::Spiral {
+                    arms: 2,
+                    turns: 1.0,
+                    spread: 0.05,
+                },
+                seed: None,
+                variation: Variation {
+                    scale: (0.5, 1.5),
+                    rotate: true,
+                    brightness: (0.6, 1.0),
+                    color_jitter: 0.05,
+                },
+            },
+            MeshConfig {
+                count: 10,
+                sprite: "star_flare".to_string(),
+                area: vec3(1000., 1000., 10.),
+                distribution: DistributionFn::Gaussian { sigma: 0.3 },
+                seed: None,
+                variation: Variation::default(),
+            },
+        ],
+    };
+    let mesh = mesh_builder.gen_mesh().expect("generating stars").mesh;
+    save_gltf(
+        "starfield.gltf",
+        &[GltfMesh {
+            name: "starfield",
+            mesh: &mesh,
+            transform: Mat4::identity(),
+            texture: Some("../assets/STSCI-H-p1917b-q-5198x4801.png"),
+        }],
+    )
+    .expect("exporting starfield");
+}
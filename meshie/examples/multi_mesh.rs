@@ -1,5 +1,5 @@
 use bevy::{diagnostic::{FrameTimeDiagnosticsPlugin, PrintDiagnosticsPlugin}, math::vec2, math::vec3, prelude::*, render::camera::PerspectiveProjection};
-use meshie::{generator::DistributionFn, generator::MeshBuilder, generator::MeshConfig};
+use meshie::{atlas::Atlas, generator::DistributionFn, generator::MeshBuilder, generator::MeshConfig, generator::Variation};
 // use rand::{FromEntropy, Rng, StdRng};
 
 fn main() {
@@ -41,46 +41,71 @@ fn setup(
         .load("../assets/STSCI-H-p1917b-q-5198x4801.png")
         .unwrap();
     let mut mesh_builder = MeshBuilder {
-        texture_size: vec2(5198., 4807.),
+        atlas: Atlas::new(vec2(5198., 4807.))
+            .with_sprite(
+                "star_small",
+                bevy::sprite::Rect {
+                    min: vec2(592., 863.),
+                    max: vec2(601., 871.),
+                },
+            )
+            .with_sprite(
+                "star_medium",
+                bevy::sprite::Rect {
+                    min: vec2(674., 857.),
+                    max: vec2(685., 869.),
+                },
+            )
+            .with_sprite(
+                "star_large",
+                bevy::sprite::Rect {
+                    min: vec2(526., 854.),
+                    max: vec2(543., 871.),
+                },
+            )
+            .with_sprite(
+                "star_flare",
+                bevy::sprite::Rect {
+                    min: vec2(613., 880.),
+                    max: vec2(656., 917.),
+                },
+            ),
+        seed: 0,
         config: vec![],
     };
     mesh_builder.config.push(MeshConfig {
         count: 100,
-        texture_position: bevy::sprite::Rect {
-            min: vec2(592., 863.),
-            max: vec2(601., 871.),
-        },
+        sprite: "star_small".to_string(),
         area: vec3(1000., 1000., 10.),
         distribution: DistributionFn::Random,
+        seed: None,
+        variation: Variation::default(),
     });
     mesh_builder.config.push(MeshConfig {
         count: 50,
-        texture_position: bevy::sprite::Rect {
-            min: vec2(674., 857.),
-            max: vec2(685., 869.),
-        },
+        sprite: "star_medium".to_string(),
         area: vec3(1000., 1000., 10.),
         distribution: DistributionFn::Random,
+        seed: None,
+        variation: Variation::default(),
     });
     mesh_builder.config.push(MeshConfig {
         count: 50,
-        texture_position: bevy::sprite::Rect {
-            min: vec2(526., 854.),
-            max: vec2(543., 871.),
-        },
+        sprite: "star_large".to_string(),
         area: vec3(1000., 1000., 10.),
         distribution: DistributionFn::Random,
+        seed: None,
+        variation: Variation::default(),
     });
     mesh_builder.config.push(MeshConfig {
         count: 10,
-        texture_position: bevy::sprite::Rect {
-            min: vec2(613., 880.),
-            max: vec2(656., 917.),
-        },
+        sprite: "star_flare".to_string(),
         area: vec3(1000., 1000., 10.),
         distribution: DistributionFn::Random,
+        seed: None,
+        variation: Variation::default(),
     });
-    let mesh = mesh_builder.gen_mesh();
+    let mesh = mesh_builder.gen_mesh().expect("generating stars").mesh;
 
     // let mut rng = StdRng::from_entropy();
     let mesh_handle = meshes.add(mesh);
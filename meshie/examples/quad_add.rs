@@ -43,8 +43,8 @@ fn setup(
         size: vec2(100., 100.),
         flip: false,
     });
-    let indices = mesh.add_mesh(&mesh_two);
-    mesh.translate_mesh(indices, vec3(10., 0.0, -5.0));
+    let indices = mesh.add_mesh(&mesh_two).expect("adding quad");
+    mesh.translate_mesh(indices, vec3(10., 0.0, -5.0)).expect("moving quad");
 
     println!("{:?}", indices);
     // reverse_triangles(&mut mesh);
@@ -77,9 +77,9 @@ fn rotate_me_baby_one_more_time(mut meshes: ResMut<Assets<Mesh>>, mut query: Que
     for meshindy in &mut query.iter() {
         if let Some(mesh) = meshes.get_mut(&meshindy.handle) {
             let quat = Quat::from_rotation_z(0.01);
-            mesh.rotate_mesh(meshindy.range, quat);
-            mesh.extend_mesh(meshindy.range, vec3(-0.1, 0.0, 0.0));
-            mesh.translate_mesh(meshindy.range, vec3(0.0, -0.2, 0.0))
+            mesh.rotate_mesh(meshindy.range, quat).expect("rotating quad");
+            mesh.extend_mesh(meshindy.range, vec3(-0.1, 0.0, 0.0)).expect("extending quad");
+            mesh.translate_mesh(meshindy.range, vec3(0.0, -0.2, 0.0)).expect("moving quad");
         }
 
     }
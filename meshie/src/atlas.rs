@@ -0,0 +1,95 @@
+use bevy::{
+    math::{vec2, Vec2},
+    sprite::Rect,
+};
+use serde::{Deserialize, Serialize};
+
+/// Names for the sprites packed into one texture, so the generator can be pointed at a sprite
+/// without knowing where it sits in the texture.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Atlas {
+    /// the texture's size in pixels
+    pub size: Vec2,
+    pub sprites: Vec<AtlasSprite>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AtlasSprite {
+    pub name: String,
+    /// in pixels, from the top left of the texture
+    #[serde(with = "RectDef")]
+    pub rect: Rect,
+}
+
+// bevy's rect isn't serde, this reads and writes it as `(min: (x, y), max: (x, y))`
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Rect", deny_unknown_fields)]
+struct RectDef {
+    min: Vec2,
+    max: Vec2,
+}
+
+impl Atlas {
+    pub fn new(size: Vec2) -> Self {
+        Self {
+            size,
+            sprites: Vec::new(),
+        }
+    }
+
+    pub fn with_sprite(mut self, name: &str, rect: Rect) -> Self {
+        self.sprites.push(AtlasSprite {
+            name: name.to_string(),
+            rect,
+        });
+        self
+    }
+
+    /// the pixel rect of the sprite called `name`
+    pub fn get(&self, name: &str) -> Option<Rect> {
+        self.sprites
+            .iter()
+            .find(|sprite| sprite.name == name)
+            .map(|sprite| sprite.rect)
+    }
+
+    /// the sprite's corners as uvs, top left then bottom right
+    pub fn uvs(&self, name: &str) -> Option<(Vec2, Vec2)> {
+        self.get(name).map(|rect| {
+            (
+                vec2(rect.min.x() / self.size.x(), rect.min.y() / self.size.y()),
+                vec2(rect.max.x() / self.size.x(), rect.max.y() / self.size.y()),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sprites_are_found_by_name() {
+        let atlas = Atlas::new(vec2(100., 50.))
+            .with_sprite(
+                "dot",
+                Rect {
+                    min: vec2(0., 0.),
+                    max: vec2(10., 10.),
+                },
+            )
+            .with_sprite(
+                "flare",
+                Rect {
+                    min: vec2(50., 25.),
+                    max: vec2(100., 50.),
+                },
+            );
+        assert_eq!(atlas.get("flare").map(|r| r.min), Some(vec2(50., 25.)));
+        assert_eq!(atlas.uvs("flare"), Some((vec2(0.5, 0.5), vec2(1.0, 1.0))));
+        assert_eq!(atlas.uvs("dot"), Some((vec2(0.0, 0.0), vec2(0.1, 0.2))));
+        assert!(atlas.get("Dot").is_none());
+    }
+}
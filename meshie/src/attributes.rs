@@ -0,0 +1,302 @@
+use bevy::{
+    prelude::Mesh,
+    render::mesh::{VertexAttribute, VertexAttributeValues},
+};
+
+use ds_range::Range;
+
+use crate::{indices_to_u32, MeshIssue, MeshieError};
+
+/// per-vertex rgba color, multiplied into the output color by the forward and star shaders
+pub const COLOR: &str = "Vertex_Color";
+/// per-vertex tangent, w holds the handedness of the bitangent
+pub const TANGENT: &str = "Vertex_Tangent";
+/// color given to vertices that are padded out when a colored and uncolored mesh are merged
+pub const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+pub fn color(colors: Vec<[f32; 4]>) -> VertexAttribute {
+    VertexAttribute {
+        name: COLOR.into(),
+        values: VertexAttributeValues::Float4(colors),
+    }
+}
+
+pub fn attribute<'a>(mesh: &'a Mesh, name: &str) -> Result<&'a VertexAttributeValues, MeshieError> {
+    mesh.attributes
+        .iter()
+        .find(|attribute| attribute.name == name)
+        .map(|attribute| &attribute.values)
+        .ok_or_else(|| MeshieError::MissingAttribute(name.to_string()))
+}
+pub fn attribute_mut<'a>(
+    mesh: &'a mut Mesh,
+    name: &str,
+) -> Result<&'a mut VertexAttributeValues, MeshieError> {
+    mesh.attributes
+        .iter_mut()
+        .find(|attribute| attribute.name == name)
+        .map(|attribute| &mut attribute.values)
+        .ok_or_else(|| MeshieError::MissingAttribute(name.to_string()))
+}
+
+/// number of vertices in the mesh, taken from its positions
+pub fn vertex_count(mesh: &Mesh) -> Result<usize, MeshieError> {
+    Ok(positions(mesh)?.len())
+}
+/// every vertex in the mesh
+pub fn vertex_range(mesh: &Mesh) -> Result<Range, MeshieError> {
+    Ok(Range {
+        start: 0,
+        end: vertex_count(mesh)?,
+    })
+}
+
+/// the number of vertices, once every attribute holds a value for each of them and every
+/// index points at one. writers check this first, so a file never refers to a missing vertex
+pub(crate) fn check_buffers(mesh: &Mesh) -> Result<usize, MeshieError> {
+    let vertex_count = vertex_count(mesh)?;
+    for attribute in mesh.attributes.iter() {
+        if attribute.values.len() != vertex_count {
+            return Err(MeshieError::Invalid(MeshIssue::AttributeLength {
+                name: attribute.name.to_string(),
+                len: attribute.values.len(),
+            }));
+        }
+    }
+    if let Some(indices) = mesh.indices.as_ref() {
+        let values = indices_to_u32(indices);
+        if let Some(i) = values.iter().position(|i| *i as usize >= vertex_count) {
+            return Err(MeshieError::Invalid(MeshIssue::IndexOutOfRange {
+                triangle: i / 3,
+                index: values[i] as usize,
+            }));
+        }
+    }
+    Ok(vertex_count)
+}
+/// errors if `vertices` isn't a valid range of the mesh's vertices
+pub(crate) fn check_range(mesh: &Mesh, vertices: Range) -> Result<(), MeshieError> {
+    check_values(positions(mesh)?, vertices)
+}
+/// like `check_range`, against the values of a single attribute
+pub(crate) fn check_values<T>(values: &[T], vertices: Range) -> Result<(), MeshieError> {
+    if vertices.start > vertices.end || vertices.end > values.len() {
+        return Err(MeshieError::OutOfBounds {
+            vertices,
+            vertex_count: values.len(),
+        });
+    }
+    Ok(())
+}
+/// errors unless there's one of `values` for each vertex in the range
+pub(crate) fn check_length<T>(values: &[T], vertices: Range) -> Result<(), MeshieError> {
+    if values.len() != vertices.len() {
+        return Err(MeshieError::WrongLength {
+            expected: vertices.len(),
+            found: values.len(),
+        });
+    }
+    Ok(())
+}
+
+pub fn positions(mesh: &Mesh) -> Result<&Vec<[f32; 3]>, MeshieError> {
+    float3(mesh, VertexAttribute::POSITION)
+}
+pub fn positions_mut(mesh: &mut Mesh) -> Result<&mut Vec<[f32; 3]>, MeshieError> {
+    float3_mut(mesh, VertexAttribute::POSITION)
+}
+pub fn normals(mesh: &Mesh) -> Result<&Vec<[f32; 3]>, MeshieError> {
+    float3(mesh, VertexAttribute::NORMAL)
+}
+pub fn normals_mut(mesh: &mut Mesh) -> Result<&mut Vec<[f32; 3]>, MeshieError> {
+    float3_mut(mesh, VertexAttribute::NORMAL)
+}
+pub fn uvs(mesh: &Mesh) -> Result<&Vec<[f32; 2]>, MeshieError> {
+    float2(mesh, VertexAttribute::UV)
+}
+pub fn uvs_mut(mesh: &mut Mesh) -> Result<&mut Vec<[f32; 2]>, MeshieError> {
+    float2_mut(mesh, VertexAttribute::UV)
+}
+
+pub fn colors(mesh: &Mesh) -> Result<&Vec<[f32; 4]>, MeshieError> {
+    float4(mesh, COLOR)
+}
+pub fn colors_mut(mesh: &mut Mesh) -> Result<&mut Vec<[f32; 4]>, MeshieError> {
+    float4_mut(mesh, COLOR)
+}
+
+pub fn tangents(mesh: &Mesh) -> Result<&Vec<[f32; 4]>, MeshieError> {
+    float4(mesh, TANGENT)
+}
+pub fn tangents_mut(mesh: &mut Mesh) -> Result<&mut Vec<[f32; 4]>, MeshieError> {
+    float4_mut(mesh, TANGENT)
+}
+
+pub fn float2<'a>(mesh: &'a Mesh, name: &str) -> Result<&'a Vec<[f32; 2]>, MeshieError> {
+    match attribute(mesh, name)? {
+        VertexAttributeValues::Float2(ref values) => Ok(values),
+        _ => Err(wrong_type(name, "Float2")),
+    }
+}
+pub fn float2_mut<'a>(
+    mesh: &'a mut Mesh,
+    name: &str,
+) -> Result<&'a mut Vec<[f32; 2]>, MeshieError> {
+    match attribute_mut(mesh, name)? {
+        VertexAttributeValues::Float2(ref mut values) => Ok(values),
+        _ => Err(wrong_type(name, "Float2")),
+    }
+}
+pub fn float3<'a>(mesh: &'a Mesh, name: &str) -> Result<&'a Vec<[f32; 3]>, MeshieError> {
+    match attribute(mesh, name)? {
+        VertexAttributeValues::Float3(ref values) => Ok(values),
+        _ => Err(wrong_type(name, "Float3")),
+    }
+}
+pub fn float3_mut<'a>(
+    mesh: &'a mut Mesh,
+    name: &str,
+) -> Result<&'a mut Vec<[f32; 3]>, MeshieError> {
+    match attribute_mut(mesh, name)? {
+        VertexAttributeValues::Float3(ref mut values) => Ok(values),
+        _ => Err(wrong_type(name, "Float3")),
+    }
+}
+
+pub fn float4<'a>(mesh: &'a Mesh, name: &str) -> Result<&'a Vec<[f32; 4]>, MeshieError> {
+    match attribute(mesh, name)? {
+        VertexAttributeValues::Float4(ref values) => Ok(values),
+        _ => Err(wrong_type(name, "Float4")),
+    }
+}
+pub fn float4_mut<'a>(
+    mesh: &'a mut Mesh,
+    name: &str,
+) -> Result<&'a mut Vec<[f32; 4]>, MeshieError> {
+    match attribute_mut(mesh, name)? {
+        VertexAttributeValues::Float4(ref mut values) => Ok(values),
+        _ => Err(wrong_type(name, "Float4")),
+    }
+}
+
+/// gives the mesh a color attribute, filled with white, if it doesn't have one yet
+pub fn ensure_colors(mesh: &mut Mesh) -> Result<&mut Vec<[f32; 4]>, MeshieError> {
+    if let Err(MeshieError::MissingAttribute(_)) = colors(mesh) {
+        let count = vertex_count(mesh)?;
+        mesh.attributes.push(color(vec![WHITE; count]));
+    }
+    colors_mut(mesh)
+}
+
+/// appends `other` onto `values` when both hold the same kind of data
+pub(crate) fn append_values(
+    name: &str,
+    values: &mut VertexAttributeValues,
+    other: &VertexAttributeValues,
+) -> Result<(), MeshieError> {
+    match (values, other) {
+        (VertexAttributeValues::Float(ref mut values), VertexAttributeValues::Float(addons)) => {
+            values.extend(addons)
+        }
+        (VertexAttributeValues::Float2(ref mut values), VertexAttributeValues::Float2(addons)) => {
+            values.extend(addons)
+        }
+        (VertexAttributeValues::Float3(ref mut values), VertexAttributeValues::Float3(addons)) => {
+            values.extend(addons)
+        }
+        (VertexAttributeValues::Float4(ref mut values), VertexAttributeValues::Float4(addons)) => {
+            values.extend(addons)
+        }
+        (values, _) => return Err(wrong_type(name, type_name(values))),
+    }
+    Ok(())
+}
+
+/// appends a copy of the value at each of `sources`
+pub(crate) fn push_copies(values: &mut VertexAttributeValues, sources: &[usize]) {
+    match values {
+        VertexAttributeValues::Float(ref mut values) => {
+            values.extend(sources.iter().map(|i| values[*i]).collect::<Vec<_>>())
+        }
+        VertexAttributeValues::Float2(ref mut values) => {
+            values.extend(sources.iter().map(|i| values[*i]).collect::<Vec<_>>())
+        }
+        VertexAttributeValues::Float3(ref mut values) => {
+            values.extend(sources.iter().map(|i| values[*i]).collect::<Vec<_>>())
+        }
+        VertexAttributeValues::Float4(ref mut values) => {
+            values.extend(sources.iter().map(|i| values[*i]).collect::<Vec<_>>())
+        }
+    }
+}
+
+/// the values at each of `sources`, in that order
+pub(crate) fn gather_values(
+    values: &VertexAttributeValues,
+    sources: &[usize],
+) -> VertexAttributeValues {
+    match values {
+        VertexAttributeValues::Float(values) => {
+            VertexAttributeValues::Float(sources.iter().map(|i| values[*i]).collect())
+        }
+        VertexAttributeValues::Float2(values) => {
+            VertexAttributeValues::Float2(sources.iter().map(|i| values[*i]).collect())
+        }
+        VertexAttributeValues::Float3(values) => {
+            VertexAttributeValues::Float3(sources.iter().map(|i| values[*i]).collect())
+        }
+        VertexAttributeValues::Float4(values) => {
+            VertexAttributeValues::Float4(sources.iter().map(|i| values[*i]).collect())
+        }
+    }
+}
+
+/// appends a value of all zeroes
+pub(crate) fn push_zero(values: &mut VertexAttributeValues) {
+    match values {
+        VertexAttributeValues::Float(ref mut values) => values.push(0.0),
+        VertexAttributeValues::Float2(ref mut values) => values.push([0.0; 2]),
+        VertexAttributeValues::Float3(ref mut values) => values.push([0.0; 3]),
+        VertexAttributeValues::Float4(ref mut values) => values.push([0.0; 4]),
+    }
+}
+
+/// an empty set of values holding the same kind of data as `values`
+pub(crate) fn empty_like(values: &VertexAttributeValues) -> VertexAttributeValues {
+    match values {
+        VertexAttributeValues::Float(_) => VertexAttributeValues::Float(vec![]),
+        VertexAttributeValues::Float2(_) => VertexAttributeValues::Float2(vec![]),
+        VertexAttributeValues::Float3(_) => VertexAttributeValues::Float3(vec![]),
+        VertexAttributeValues::Float4(_) => VertexAttributeValues::Float4(vec![]),
+    }
+}
+
+/// drops the values in `vertices`, shifting everything after them down
+pub(crate) fn remove_values(values: &mut VertexAttributeValues, vertices: Range) {
+    match values {
+        VertexAttributeValues::Float(ref mut values) => drop(values.drain(vertices.iter())),
+        VertexAttributeValues::Float2(ref mut values) => drop(values.drain(vertices.iter())),
+        VertexAttributeValues::Float3(ref mut values) => drop(values.drain(vertices.iter())),
+        VertexAttributeValues::Float4(ref mut values) => drop(values.drain(vertices.iter())),
+    }
+}
+
+pub(crate) fn same_type(values: &VertexAttributeValues, other: &VertexAttributeValues) -> bool {
+    type_name(values) == type_name(other)
+}
+
+pub(crate) fn type_name(values: &VertexAttributeValues) -> &'static str {
+    match values {
+        VertexAttributeValues::Float(_) => "Float",
+        VertexAttributeValues::Float2(_) => "Float2",
+        VertexAttributeValues::Float3(_) => "Float3",
+        VertexAttributeValues::Float4(_) => "Float4",
+    }
+}
+
+fn wrong_type(name: &str, expected: &'static str) -> MeshieError {
+    MeshieError::WrongAttributeType {
+        name: name.to_string(),
+        expected,
+    }
+}
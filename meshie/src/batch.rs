@@ -0,0 +1,352 @@
+use bevy::{
+    math::{Quat, Vec3},
+    prelude::Mesh,
+};
+use ds_range::Range;
+
+use crate::{attributes, Aabb, BoundingSphere, Meshie, MeshieError};
+
+/// Handle to a mesh added to a `MeshieBatch`. It keeps pointing at the same vertices while
+/// other sub-meshes are added and removed, and goes stale once its own sub-mesh is removed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct SubMeshId {
+    index: u32,
+    generation: u32,
+}
+
+#[derive(Debug)]
+struct Slot {
+    generation: u32,
+    vertices: Option<Range>,
+    /// cached so the batch's bounds can be found without touching any vertices
+    bounds: Option<(Aabb, BoundingSphere)>,
+}
+
+/// Keeps track of the sub-meshes of a `Mesh` made out of many, each addressed by a
+/// `SubMeshId` instead of a raw vertex range. Bounds are kept per sub-mesh and only rebuilt
+/// for the sub-mesh that changed.
+///
+/// The batch doesn't own the mesh, so the mesh can stay in `Assets<Mesh>` behind its handle.
+/// Every call that touches vertices takes the mesh, which has to start out empty and only
+/// change through the batch. The batch remembers how many vertices the mesh should have and
+/// refuses any other mesh with `MeshieError::BatchMismatch`.
+#[derive(Debug, Default)]
+pub struct MeshieBatch {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    vertex_count: usize,
+}
+
+impl MeshieBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// errors unless `mesh` has as many vertices as the batch has put in it, which catches
+    /// being handed another mesh or one edited behind the batch's back
+    pub fn check_mesh(&self, mesh: &Mesh) -> Result<(), MeshieError> {
+        // a mesh with no attributes yet has no vertices
+        let found = attributes::vertex_count(mesh).unwrap_or(0);
+        if found != self.vertex_count {
+            return Err(MeshieError::BatchMismatch {
+                expected: self.vertex_count,
+                found,
+            });
+        }
+        Ok(())
+    }
+
+    /// the vertices currently used by `id`
+    pub fn range(&self, id: SubMeshId) -> Option<Range> {
+        match self.slots.get(id.index as usize) {
+            Some(slot) if slot.generation == id.generation => slot.vertices,
+            _ => None,
+        }
+    }
+    pub fn contains(&self, id: SubMeshId) -> bool {
+        self.range(id).is_some()
+    }
+    /// number of live sub-meshes
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn ids(&self) -> impl Iterator<Item = SubMeshId> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.vertices.is_some())
+            .map(|(index, slot)| SubMeshId {
+                index: index as u32,
+                generation: slot.generation,
+            })
+    }
+
+    /// appends `other` to `mesh` as a new sub-mesh
+    pub fn add(&mut self, mesh: &mut Mesh, other: &Mesh) -> Result<SubMeshId, MeshieError> {
+        self.check_mesh(mesh)?;
+        let vertices = mesh.add_mesh(other)?;
+        self.vertex_count = vertices.end;
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    vertices: None,
+                    bounds: None,
+                });
+                self.slots.len() as u32 - 1
+            }
+        };
+        let slot = &mut self.slots[index as usize];
+        slot.vertices = Some(vertices);
+        slot.bounds = measure(mesh, vertices);
+        Ok(SubMeshId {
+            index,
+            generation: slot.generation,
+        })
+    }
+    /// removes the sub-mesh, moving every sub-mesh after it down to fill the gap
+    pub fn remove(&mut self, mesh: &mut Mesh, id: SubMeshId) -> Result<(), MeshieError> {
+        let vertices = self.get(id)?;
+        self.check_mesh(mesh)?;
+        let remap = mesh.remove_mesh(vertices)?;
+        self.vertex_count -= vertices.len();
+        let slot = &mut self.slots[id.index as usize];
+        slot.vertices = None;
+        slot.bounds = None;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(id.index);
+        for slot in self.slots.iter_mut() {
+            if let Some(vertices) = slot.vertices {
+                slot.vertices = remap.range(vertices);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn translate(
+        &mut self,
+        mesh: &mut Mesh,
+        id: SubMeshId,
+        translation: Vec3,
+    ) -> Result<(), MeshieError> {
+        let vertices = self.get(id)?;
+        self.check_mesh(mesh)?;
+        mesh.translate_mesh(vertices, translation)?;
+        let slot = &mut self.slots[id.index as usize];
+        slot.bounds = slot
+            .bounds
+            .map(|(aabb, sphere)| (aabb.translated(translation), sphere.translated(translation)));
+        Ok(())
+    }
+    pub fn rotate(
+        &mut self,
+        mesh: &mut Mesh,
+        id: SubMeshId,
+        rotation: Quat,
+    ) -> Result<(), MeshieError> {
+        let vertices = self.get(id)?;
+        self.check_mesh(mesh)?;
+        mesh.rotate_mesh(vertices, rotation)?;
+        self.slots[id.index as usize].bounds = measure(mesh, vertices);
+        Ok(())
+    }
+    pub fn set_uvs(
+        &mut self,
+        mesh: &mut Mesh,
+        id: SubMeshId,
+        uv: Vec<[f32; 2]>,
+    ) -> Result<(), MeshieError> {
+        let vertices = self.get(id)?;
+        self.check_mesh(mesh)?;
+        mesh.set_uvs(vertices, uv)
+    }
+
+    pub fn aabb(&self, id: SubMeshId) -> Result<Aabb, MeshieError> {
+        self.get(id)?;
+        let (aabb, _) = self.slots[id.index as usize]
+            .bounds
+            .ok_or(MeshieError::EmptyRange)?;
+        Ok(aabb)
+    }
+    pub fn bounding_sphere(&self, id: SubMeshId) -> Result<BoundingSphere, MeshieError> {
+        self.get(id)?;
+        let (_, sphere) = self.slots[id.index as usize]
+            .bounds
+            .ok_or(MeshieError::EmptyRange)?;
+        Ok(sphere)
+    }
+    /// box around every sub-mesh, built from the cached bounds
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.slots
+            .iter()
+            .filter_map(|slot| slot.bounds.map(|(aabb, _)| aabb))
+            .fold(None, |total: Option<Aabb>, aabb| {
+                Some(total.map_or(aabb, |total| total.union(&aabb)))
+            })
+    }
+
+    fn get(&self, id: SubMeshId) -> Result<Range, MeshieError> {
+        self.range(id).ok_or(MeshieError::UnknownSubMesh(id))
+    }
+}
+
+fn measure(mesh: &Mesh, vertices: Range) -> Option<(Aabb, BoundingSphere)> {
+    let aabb = mesh.aabb(vertices).ok()?;
+    let sphere = mesh.bounding_sphere(vertices).ok()?;
+    Some((aabb, sphere))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{
+        math::{vec2, vec3},
+        prelude::shape,
+        render::pipeline::PrimitiveTopology,
+    };
+
+    fn quad() -> Mesh {
+        Mesh::from(shape::Quad {
+            size: vec2(2., 2.),
+            flip: false,
+        })
+    }
+    fn empty() -> Mesh {
+        Mesh::new(PrimitiveTopology::TriangleList)
+    }
+
+    #[test]
+    fn ids_survive_removal() {
+        let (mut batch, mut mesh) = (MeshieBatch::new(), empty());
+        let one = batch.add(&mut mesh, &quad()).unwrap();
+        let two = batch.add(&mut mesh, &quad()).unwrap();
+        let three = batch.add(&mut mesh, &quad()).unwrap();
+        batch
+            .translate(&mut mesh, three, vec3(0.0, 0.0, 3.0))
+            .unwrap();
+        let three_positions = mesh.get_positions(batch.range(three).unwrap());
+
+        batch.remove(&mut mesh, two).unwrap();
+        assert_eq!(batch.len(), 2);
+        assert!(!batch.contains(two));
+        assert_eq!(batch.range(one), Some(Range { start: 0, end: 4 }));
+        assert_eq!(batch.range(three), Some(Range { start: 4, end: 8 }));
+        assert_eq!(
+            mesh.get_positions(batch.range(three).unwrap()),
+            three_positions
+        );
+        assert_eq!(attributes::vertex_count(&mesh), Ok(8));
+    }
+
+    #[test]
+    fn stale_ids_are_rejected() {
+        let (mut batch, mut mesh) = (MeshieBatch::new(), empty());
+        let one = batch.add(&mut mesh, &quad()).unwrap();
+        batch.remove(&mut mesh, one).unwrap();
+        let two = batch.add(&mut mesh, &quad()).unwrap();
+        assert_ne!(one, two);
+        assert_eq!(
+            batch.remove(&mut mesh, one),
+            Err(MeshieError::UnknownSubMesh(one))
+        );
+        assert_eq!(
+            batch.translate(&mut mesh, one, vec3(1.0, 0.0, 0.0)),
+            Err(MeshieError::UnknownSubMesh(one))
+        );
+        assert_eq!(batch.ids().collect::<Vec<_>>(), vec![two]);
+        assert_eq!(batch.range(two), Some(Range { start: 0, end: 4 }));
+    }
+
+    #[test]
+    fn other_meshes_are_refused() {
+        let (mut batch, mut mesh) = (MeshieBatch::new(), empty());
+        let one = batch.add(&mut mesh, &quad()).unwrap();
+        let mismatch = Err(MeshieError::BatchMismatch {
+            expected: 4,
+            found: 8,
+        });
+        // a mesh that was added to behind the batch's back
+        let mut edited = quad();
+        edited.add_mesh(&quad()).unwrap();
+        assert_eq!(batch.check_mesh(&edited), mismatch);
+        assert_eq!(batch.add(&mut edited, &quad()).map(|_| ()), mismatch);
+        assert_eq!(batch.remove(&mut edited, one), mismatch);
+        assert_eq!(
+            batch.translate(&mut edited, one, vec3(1.0, 0.0, 0.0)),
+            mismatch
+        );
+        assert_eq!(attributes::vertex_count(&edited), Ok(8));
+        // a batch starts from an empty mesh
+        assert!(MeshieBatch::new().add(&mut quad(), &quad()).is_err());
+        assert_eq!(batch.check_mesh(&mesh), Ok(()));
+    }
+
+    #[test]
+    fn edits_by_id() {
+        let (mut batch, mut mesh) = (MeshieBatch::new(), empty());
+        let _one = batch.add(&mut mesh, &quad()).unwrap();
+        let two = batch.add(&mut mesh, &quad()).unwrap();
+        batch
+            .translate(&mut mesh, two, vec3(5.0, 0.0, 0.0))
+            .unwrap();
+        batch
+            .rotate(&mut mesh, two, Quat::from_rotation_z(std::f32::consts::PI))
+            .unwrap();
+        batch.set_uvs(&mut mesh, two, vec![[0.5, 0.5]; 4]).unwrap();
+
+        let range = batch.range(two).unwrap();
+        let center = mesh.get_center(range).unwrap();
+        assert!((center - vec3(5.0, 0.0, 0.0)).length() < 0.0001);
+        assert_eq!(
+            attributes::uvs(&mesh).unwrap()[range.iter()].to_vec(),
+            vec![[0.5, 0.5]; 4]
+        );
+        assert_eq!(
+            attributes::uvs(&mesh).unwrap()[0..4].to_vec(),
+            attributes::uvs(&quad()).unwrap().clone()
+        );
+    }
+
+    #[test]
+    fn cached_bounds_follow_edits() {
+        let (mut batch, mut mesh) = (MeshieBatch::new(), empty());
+        let one = batch.add(&mut mesh, &quad()).unwrap();
+        let two = batch.add(&mut mesh, &quad()).unwrap();
+        batch
+            .translate(&mut mesh, two, vec3(10.0, 0.0, 0.0))
+            .unwrap();
+        assert_eq!(
+            batch.aabb(two),
+            Ok(Aabb {
+                min: vec3(9.0, -1.0, 0.0),
+                max: vec3(11.0, 1.0, 0.0)
+            })
+        );
+        assert_eq!(
+            batch.bounding_sphere(two).unwrap().center,
+            vec3(10.0, 0.0, 0.0)
+        );
+
+        batch
+            .rotate(
+                &mut mesh,
+                two,
+                Quat::from_rotation_x(std::f32::consts::FRAC_PI_2),
+            )
+            .unwrap();
+        let aabb = batch.aabb(two).unwrap();
+        assert_eq!(Ok(aabb), mesh.aabb(batch.range(two).unwrap()));
+        assert!(aabb.half_extents().z() > 0.9);
+        assert!(aabb.half_extents().y() < 0.0001);
+
+        assert_eq!(batch.bounds().unwrap().min.x(), -1.0);
+        assert_eq!(batch.bounds().unwrap().max.x(), 11.0);
+        batch.remove(&mut mesh, one).unwrap();
+        assert_eq!(batch.bounds().unwrap().min.x(), 9.0);
+        assert_eq!(batch.aabb(one), Err(MeshieError::UnknownSubMesh(one)));
+    }
+}
@@ -0,0 +1,117 @@
+use bevy::math::{Mat4, Vec3};
+
+/// Axis aligned box around a set of vertices.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// the smallest box holding every point, `None` if there are none
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(
+            Aabb {
+                min: first,
+                max: first,
+            },
+            |aabb, point| Aabb {
+                min: aabb.min.min(point),
+                max: aabb.max.max(point),
+            },
+        ))
+    }
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+    pub fn half_extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+    pub fn contains(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.cmple(other.max).all() && self.max.cmpge(other.min).all()
+    }
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+    pub fn translated(&self, translation: Vec3) -> Aabb {
+        Aabb {
+            min: self.min + translation,
+            max: self.max + translation,
+        }
+    }
+}
+
+/// Sphere around a set of vertices, centered on their `Aabb`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// a sphere centered on the points' box, just big enough to hold all of them
+    pub fn from_points(points: &[Vec3]) -> Option<Self> {
+        let center = Aabb::from_points(points.iter().cloned())?.center();
+        let radius = points
+            .iter()
+            .map(|point| (*point - center).length())
+            .fold(0.0, f32::max);
+        Some(BoundingSphere { center, radius })
+    }
+    pub fn contains(&self, point: Vec3) -> bool {
+        (point - self.center).length_squared() <= self.radius * self.radius
+    }
+    pub fn intersects(&self, other: &BoundingSphere) -> bool {
+        let reach = self.radius + other.radius;
+        (other.center - self.center).length_squared() <= reach * reach
+    }
+    pub fn translated(&self, translation: Vec3) -> BoundingSphere {
+        BoundingSphere {
+            center: self.center + translation,
+            radius: self.radius,
+        }
+    }
+    /// the sphere moved into the space of `transform`. the radius grows by the largest scale
+    /// along any axis, so a squashed sphere is still held inside
+    pub fn transformed(&self, transform: &Mat4) -> BoundingSphere {
+        let scale = [Vec3::unit_x(), Vec3::unit_y(), Vec3::unit_z()]
+            .iter()
+            .map(|axis| transform.transform_vector3(*axis).length())
+            .fold(0.0, f32::max);
+        BoundingSphere {
+            center: transform.transform_point3(self.center),
+            radius: self.radius * scale,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::{vec3, Quat};
+
+    #[test]
+    fn transformed_spheres_take_the_largest_scale() {
+        let sphere = BoundingSphere {
+            center: vec3(1.0, 0.0, 0.0),
+            radius: 2.0,
+        };
+        let transform = Mat4::from_scale_rotation_translation(
+            vec3(1.0, 3.0, 0.5),
+            Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+            vec3(0.0, 0.0, 10.0),
+        );
+        let moved = sphere.transformed(&transform);
+        assert!((moved.center - vec3(0.0, 1.0, 10.0)).length() < 0.0001);
+        assert!((moved.radius - 6.0).abs() < 0.0001);
+        assert_eq!(sphere.transformed(&Mat4::identity()), sphere);
+    }
+}
@@ -0,0 +1,571 @@
+//! A compact binary file for meshes that are slow to generate, like the starfields.
+//!
+//! This is a building block for the library's users: nothing in meshie or the game loads
+//! through it yet. Callers pick the path and decide when a cached file is out of date.
+//!
+//! Everything is little endian. The file starts with a fixed size header:
+//!
+//! | field          | type      |
+//! |----------------|-----------|
+//! | magic          | `b"MSHE"` |
+//! | version        | u32       |
+//! | body length    | u64       |
+//!
+//! followed by the body:
+//!
+//! - topology, vertex count, attribute count, index format (0 none, 1 U16, 2 U32), index count
+//!   and sub-mesh count, each a u32
+//! - the attribute table, a u32 name length, the utf-8 name and a u32 component count for each
+//!   attribute
+//! - the values of each attribute in table order, as f32
+//! - the index buffer
+//! - the sub-mesh range table, a u32 start and end for each range
+//!
+//! and last a u32 checksum, 32 bit FNV-1a over the body. It goes after the body so the writer
+//! can hash the body as it streams it out. Readers refuse files whose indices or sub-mesh
+//! ranges reach past the vertices, so a loaded mesh is always safe to hand to `Meshie`.
+
+use std::{
+    convert::TryInto,
+    fmt,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use bevy::{
+    prelude::Mesh,
+    render::{
+        mesh::{Indices, VertexAttribute, VertexAttributeValues},
+        pipeline::PrimitiveTopology,
+    },
+};
+use ds_range::Range;
+
+use crate::{attributes, indices_to_u32, MeshieError};
+
+pub const MAGIC: [u8; 4] = *b"MSHE";
+/// bumped whenever the layout changes, files from other versions are refused
+pub const VERSION: u32 = 2;
+const HEADER_LEN: usize = 16;
+const FNV_OFFSET: u32 = 0x811c_9dc5;
+
+#[derive(Debug)]
+pub enum CacheError {
+    Io(io::Error),
+    /// the file doesn't start with `MAGIC`
+    NotACache,
+    UnsupportedVersion(u32),
+    ChecksumMismatch {
+        expected: u32,
+        found: u32,
+    },
+    /// the body ended early or holds a value that can't be right
+    Corrupt(&'static str),
+    /// the mesh can't be written as is
+    Mesh(MeshieError),
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Io(error) => write!(f, "mesh cache io error: {}", error),
+            CacheError::NotACache => write!(f, "not a mesh cache file"),
+            CacheError::UnsupportedVersion(version) => write!(
+                f,
+                "mesh cache version {} isn't supported, expected {}",
+                version, VERSION
+            ),
+            CacheError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "mesh cache checksum is {:08x}, expected {:08x}",
+                found, expected
+            ),
+            CacheError::Corrupt(what) => write!(f, "mesh cache is corrupt: {}", what),
+            CacheError::Mesh(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+impl From<io::Error> for CacheError {
+    fn from(error: io::Error) -> Self {
+        CacheError::Io(error)
+    }
+}
+impl From<MeshieError> for CacheError {
+    fn from(error: MeshieError) -> Self {
+        CacheError::Mesh(error)
+    }
+}
+
+pub fn save(path: impl AsRef<Path>, mesh: &Mesh, sub_meshes: &[Range]) -> Result<(), CacheError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_mesh(&mut writer, mesh, sub_meshes)?;
+    writer.flush()?;
+    Ok(())
+}
+pub fn load(path: impl AsRef<Path>) -> Result<(Mesh, Vec<Range>), CacheError> {
+    read_mesh(&mut BufReader::new(File::open(path)?))
+}
+
+pub fn write_mesh(
+    writer: &mut impl Write,
+    mesh: &Mesh,
+    sub_meshes: &[Range],
+) -> Result<(), CacheError> {
+    let vertex_count = attributes::check_buffers(mesh)?;
+    if let Some(vertices) = sub_meshes
+        .iter()
+        .find(|r| r.start > r.end || r.end > vertex_count)
+    {
+        return Err(MeshieError::OutOfBounds {
+            vertices: *vertices,
+            vertex_count,
+        }
+        .into());
+    }
+
+    let (index_format, index_count, index_size) = match mesh.indices.as_ref() {
+        None => (0, 0, 0),
+        Some(Indices::U16(values)) => (1, values.len(), 2),
+        Some(Indices::U32(values)) => (2, values.len(), 4),
+    };
+    // the length is known from the counts, so the body can be streamed straight out
+    let table_len: usize = mesh
+        .attributes
+        .iter()
+        .map(|attribute| 8 + attribute.name.len())
+        .sum();
+    let values_len: usize = mesh
+        .attributes
+        .iter()
+        .map(|attribute| vertex_count * components(&attribute.values) * 4)
+        .sum();
+    let body_len = 24 + table_len + values_len + index_count * index_size + sub_meshes.len() * 8;
+
+    writer.write_all(&MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&(body_len as u64).to_le_bytes())?;
+
+    let mut body = Hashing {
+        writer: &mut *writer,
+        hash: FNV_OFFSET,
+        written: 0,
+    };
+    for value in [
+        topology_code(mesh.primitive_topology),
+        vertex_count as u32,
+        mesh.attributes.len() as u32,
+        index_format,
+        index_count as u32,
+        sub_meshes.len() as u32,
+    ]
+    .iter()
+    {
+        body.write_all(&value.to_le_bytes())?;
+    }
+    for attribute in mesh.attributes.iter() {
+        body.write_all(&(attribute.name.len() as u32).to_le_bytes())?;
+        body.write_all(attribute.name.as_bytes())?;
+        body.write_all(&(components(&attribute.values) as u32).to_le_bytes())?;
+    }
+    for attribute in mesh.attributes.iter() {
+        match &attribute.values {
+            VertexAttributeValues::Float(values) => body.f32s(values)?,
+            VertexAttributeValues::Float2(values) => body.f32s(values.iter().flatten())?,
+            VertexAttributeValues::Float3(values) => body.f32s(values.iter().flatten())?,
+            VertexAttributeValues::Float4(values) => body.f32s(values.iter().flatten())?,
+        }
+    }
+    match mesh.indices.as_ref() {
+        None => {}
+        Some(Indices::U16(values)) => {
+            for value in values {
+                body.write_all(&value.to_le_bytes())?;
+            }
+        }
+        Some(Indices::U32(values)) => {
+            for value in values {
+                body.write_all(&value.to_le_bytes())?;
+            }
+        }
+    }
+    for range in sub_meshes {
+        body.write_all(&(range.start as u32).to_le_bytes())?;
+        body.write_all(&(range.end as u32).to_le_bytes())?;
+    }
+    debug_assert_eq!(body.written, body_len as u64, "mesh cache body length");
+    let hash = body.hash;
+    writer.write_all(&hash.to_le_bytes())?;
+    Ok(())
+}
+
+pub fn read_mesh(reader: &mut impl Read) -> Result<(Mesh, Vec<Range>), CacheError> {
+    let mut header = [0; HEADER_LEN];
+    reader
+        .read_exact(&mut header)
+        .map_err(|error| match error.kind() {
+            io::ErrorKind::UnexpectedEof => CacheError::NotACache,
+            _ => CacheError::Io(error),
+        })?;
+    if header[0..4] != MAGIC {
+        return Err(CacheError::NotACache);
+    }
+    let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    if version != VERSION {
+        return Err(CacheError::UnsupportedVersion(version));
+    }
+    let body_len = u64::from_le_bytes(header[8..16].try_into().unwrap());
+
+    // the body is hashed as it's parsed. a body that doesn't parse is still read to the end,
+    // so damage shows up as a checksum mismatch whenever the checksum can tell
+    let mut body = Body {
+        reader: reader.by_ref().take(body_len),
+        hash: FNV_OFFSET,
+    };
+    let parsed = read_body(&mut body);
+    let trailing = body.reader.limit() > 0;
+    let found = body.finish()?;
+    let mut trailer = [0; 4];
+    reader
+        .read_exact(&mut trailer)
+        .map_err(|error| match error.kind() {
+            io::ErrorKind::UnexpectedEof => CacheError::Corrupt("the checksum is missing"),
+            _ => CacheError::Io(error),
+        })?;
+    let expected = u32::from_le_bytes(trailer);
+    if found != expected {
+        return Err(CacheError::ChecksumMismatch { expected, found });
+    }
+    let (mesh, sub_meshes, vertex_count) = parsed?;
+    if trailing {
+        return Err(CacheError::Corrupt("body has trailing bytes"));
+    }
+
+    if let Some(indices) = mesh.indices.as_ref() {
+        if indices_to_u32(indices)
+            .iter()
+            .any(|i| *i as usize >= vertex_count)
+        {
+            return Err(CacheError::Corrupt("an index is past the last vertex"));
+        }
+    }
+    if sub_meshes
+        .iter()
+        .any(|r| r.start > r.end || r.end > vertex_count)
+    {
+        return Err(CacheError::Corrupt("a sub-mesh range is out of bounds"));
+    }
+    Ok((mesh, sub_meshes))
+}
+
+/// the mesh, sub-meshes and vertex count the body describes, unchecked
+fn read_body<R: Read>(body: &mut Body<R>) -> Result<(Mesh, Vec<Range>, usize), CacheError> {
+    let topology = topology_from_code(body.u32()?)?;
+    let vertex_count = body.u32()? as usize;
+    let attribute_count = body.u32()? as usize;
+    let index_format = body.u32()?;
+    let index_count = body.u32()? as usize;
+    let sub_mesh_count = body.u32()? as usize;
+
+    let mut table = Vec::new();
+    for _ in 0..attribute_count {
+        let name_len = body.u32()? as usize;
+        let name = String::from_utf8(body.bytes(name_len)?)
+            .map_err(|_| CacheError::Corrupt("attribute name isn't utf-8"))?;
+        let components = body.u32()? as usize;
+        if components == 0 || components > 4 {
+            return Err(CacheError::Corrupt(
+                "attribute has an unknown component count",
+            ));
+        }
+        table.push((name, components));
+    }
+
+    let mut mesh = Mesh::new(topology);
+    for (name, components) in table {
+        let floats = body.f32s(vertex_count * components)?;
+        let values = match components {
+            1 => VertexAttributeValues::Float(floats),
+            2 => VertexAttributeValues::Float2(
+                floats.chunks_exact(2).map(|v| [v[0], v[1]]).collect(),
+            ),
+            3 => VertexAttributeValues::Float3(
+                floats.chunks_exact(3).map(|v| [v[0], v[1], v[2]]).collect(),
+            ),
+            _ => VertexAttributeValues::Float4(
+                floats
+                    .chunks_exact(4)
+                    .map(|v| [v[0], v[1], v[2], v[3]])
+                    .collect(),
+            ),
+        };
+        mesh.attributes.push(VertexAttribute {
+            name: name.into(),
+            values,
+        });
+    }
+    mesh.indices = match index_format {
+        0 => None,
+        1 => Some(Indices::U16(
+            body.bytes(index_count * 2)?
+                .chunks_exact(2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                .collect(),
+        )),
+        2 => Some(Indices::U32(body.u32s(index_count)?)),
+        _ => return Err(CacheError::Corrupt("unknown index format")),
+    };
+    let ranges = body.u32s(sub_mesh_count * 2)?;
+    let sub_meshes = ranges
+        .chunks_exact(2)
+        .map(|r| Range {
+            start: r[0] as usize,
+            end: r[1] as usize,
+        })
+        .collect();
+    Ok((mesh, sub_meshes, vertex_count))
+}
+
+/// 32 bit FNV-1a, carrying on from `hash`
+fn checksum(hash: u32, bytes: &[u8]) -> u32 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn components(values: &VertexAttributeValues) -> usize {
+    match values {
+        VertexAttributeValues::Float(_) => 1,
+        VertexAttributeValues::Float2(_) => 2,
+        VertexAttributeValues::Float3(_) => 3,
+        VertexAttributeValues::Float4(_) => 4,
+    }
+}
+
+fn topology_code(topology: PrimitiveTopology) -> u32 {
+    match topology {
+        PrimitiveTopology::PointList => 0,
+        PrimitiveTopology::LineList => 1,
+        PrimitiveTopology::LineStrip => 2,
+        PrimitiveTopology::TriangleList => 3,
+        PrimitiveTopology::TriangleStrip => 4,
+    }
+}
+fn topology_from_code(code: u32) -> Result<PrimitiveTopology, CacheError> {
+    Ok(match code {
+        0 => PrimitiveTopology::PointList,
+        1 => PrimitiveTopology::LineList,
+        2 => PrimitiveTopology::LineStrip,
+        3 => PrimitiveTopology::TriangleList,
+        4 => PrimitiveTopology::TriangleStrip,
+        _ => return Err(CacheError::Corrupt("unknown primitive topology")),
+    })
+}
+
+/// reads values off the body, hashing every byte on the way
+struct Body<R> {
+    reader: io::Take<R>,
+    hash: u32,
+}
+
+impl<R: Read> Body<R> {
+    fn bytes(&mut self, len: usize) -> Result<Vec<u8>, CacheError> {
+        // checked before allocating, so a damaged count can't ask for more than the file holds
+        if len as u64 > self.reader.limit() {
+            return Err(CacheError::Corrupt("body ended early"));
+        }
+        let mut bytes = vec![0; len];
+        self.reader.read_exact(&mut bytes).map_err(short_body)?;
+        self.hash = checksum(self.hash, &bytes);
+        Ok(bytes)
+    }
+    fn u32(&mut self) -> Result<u32, CacheError> {
+        Ok(u32::from_le_bytes(self.bytes(4)?[..].try_into().unwrap()))
+    }
+    fn u32s(&mut self, count: usize) -> Result<Vec<u32>, CacheError> {
+        Ok(self
+            .bytes(count * 4)?
+            .chunks_exact(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect())
+    }
+    fn f32s(&mut self, count: usize) -> Result<Vec<f32>, CacheError> {
+        Ok(self
+            .bytes(count * 4)?
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect())
+    }
+    /// hashes whatever is left of the body and returns the checksum of all of it
+    fn finish(mut self) -> Result<u32, CacheError> {
+        let mut buffer = [0; 4096];
+        while self.reader.limit() > 0 {
+            let read = self.reader.read(&mut buffer)?;
+            if read == 0 {
+                return Err(short_body(io::ErrorKind::UnexpectedEof.into()));
+            }
+            self.hash = checksum(self.hash, &buffer[..read]);
+        }
+        Ok(self.hash)
+    }
+}
+
+/// passes the body on to the file, hashing every byte on the way
+struct Hashing<W> {
+    writer: W,
+    hash: u32,
+    written: u64,
+}
+
+impl<W: Write> Hashing<W> {
+    fn f32s<'a>(&mut self, values: impl IntoIterator<Item = &'a f32>) -> io::Result<()> {
+        for value in values {
+            self.write_all(&value.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Hashing<W> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(bytes)?;
+        self.hash = checksum(self.hash, &bytes[..written]);
+        self.written += written as u64;
+        Ok(written)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+fn short_body(error: io::Error) -> CacheError {
+    match error.kind() {
+        io::ErrorKind::UnexpectedEof => CacheError::Corrupt("body is shorter than the header says"),
+        _ => CacheError::Io(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MeshIssue, Meshie};
+    use bevy::{math::vec2, prelude::shape};
+
+    fn fleet() -> (Mesh, Vec<Range>) {
+        let mut mesh = Mesh::from(shape::Quad {
+            size: vec2(2., 2.),
+            flip: false,
+        });
+        let cube = mesh
+            .add_mesh(&Mesh::from(shape::Cube { size: 1.0 }))
+            .unwrap();
+        mesh.set_colors(cube, [0.2, 0.4, 0.6, 1.0]).unwrap();
+        (mesh, vec![Range { start: 0, end: 4 }, cube])
+    }
+
+    fn bytes(mesh: &Mesh, sub_meshes: &[Range]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_mesh(&mut bytes, mesh, sub_meshes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn round_trip() {
+        let (mesh, sub_meshes) = fleet();
+        let (read, read_sub_meshes) = read_mesh(&mut &bytes(&mesh, &sub_meshes)[..]).unwrap();
+        assert_eq!(format!("{:?}", read), format!("{:?}", mesh));
+        assert_eq!(read_sub_meshes, sub_meshes);
+
+        let mut small = Mesh::from(shape::Quad {
+            size: vec2(2., 2.),
+            flip: false,
+        });
+        small.indices = Some(Indices::U16(vec![0, 2, 1, 0, 3, 2]));
+        let (read, _) = read_mesh(&mut &bytes(&small, &[])[..]).unwrap();
+        assert_eq!(format!("{:?}", read), format!("{:?}", small));
+    }
+
+    #[test]
+    fn damage_is_caught() {
+        let (mesh, sub_meshes) = fleet();
+        let good = bytes(&mesh, &sub_meshes);
+
+        // a byte in the middle of the body and one in the checksum after it
+        for at in [good.len() / 2, good.len() - 1].iter() {
+            let mut flipped = good.clone();
+            flipped[*at] ^= 1;
+            assert!(matches!(
+                read_mesh(&mut &flipped[..]),
+                Err(CacheError::ChecksumMismatch { .. })
+            ));
+        }
+
+        let mut old = good.clone();
+        old[4..8].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            read_mesh(&mut &old[..]),
+            Err(CacheError::UnsupportedVersion(v)) if v == VERSION + 1
+        ));
+
+        assert!(matches!(
+            read_mesh(&mut &good[..good.len() - 3]),
+            Err(CacheError::Corrupt(_))
+        ));
+        assert!(matches!(
+            read_mesh(&mut &b"OBJ\n"[..]),
+            Err(CacheError::NotACache)
+        ));
+    }
+
+    #[test]
+    fn inconsistent_files_are_refused() {
+        // a mesh that would pass the checksum, but whose last index or sub-mesh range
+        // reaches past the vertices
+        let (mut mesh, sub_meshes) = fleet();
+        let good = bytes(&mesh, &sub_meshes);
+        let trailer = good.len() - 4;
+        let sub_mesh_table = trailer - sub_meshes.len() * 8;
+        let mut past_the_end = good.clone();
+        past_the_end[sub_mesh_table - 4..sub_mesh_table].copy_from_slice(&28u32.to_le_bytes());
+        let mut backwards = good.clone();
+        backwards[sub_mesh_table..sub_mesh_table + 4].copy_from_slice(&5u32.to_le_bytes());
+        backwards[sub_mesh_table + 4..sub_mesh_table + 8].copy_from_slice(&4u32.to_le_bytes());
+        for bad in [past_the_end, backwards].iter_mut() {
+            let hash = checksum(FNV_OFFSET, &bad[HEADER_LEN..trailer]);
+            bad[trailer..].copy_from_slice(&hash.to_le_bytes());
+            assert!(matches!(
+                read_mesh(&mut &bad[..]),
+                Err(CacheError::Corrupt(_))
+            ));
+        }
+
+        // the writer refuses the same meshes
+        assert!(matches!(
+            write_mesh(&mut Vec::new(), &mesh, &[Range { start: 5, end: 4 }]),
+            Err(CacheError::Mesh(MeshieError::OutOfBounds { .. }))
+        ));
+        if let Some(Indices::U32(indices)) = mesh.indices.as_mut() {
+            indices.push(28);
+        }
+        assert!(matches!(
+            write_mesh(&mut Vec::new(), &mesh, &[]),
+            Err(CacheError::Mesh(MeshieError::Invalid(
+                MeshIssue::IndexOutOfRange { index: 28, .. }
+            )))
+        ));
+    }
+
+    #[test]
+    fn unwritable_meshes_are_refused() {
+        let (mesh, _) = fleet();
+        let mut bytes = Vec::new();
+        assert!(matches!(
+            write_mesh(&mut bytes, &mesh, &[Range { start: 20, end: 40 }]),
+            Err(CacheError::Mesh(MeshieError::OutOfBounds { .. }))
+        ));
+        assert!(bytes.is_empty());
+    }
+}
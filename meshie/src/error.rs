@@ -0,0 +1,78 @@
+use std::fmt;
+
+use ds_range::Range;
+
+use crate::{batch::SubMeshId, MeshIssue};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeshieError {
+    /// the mesh has no vertex attribute with this name
+    MissingAttribute(String),
+    /// the attribute exists but doesn't hold the expected kind of values
+    WrongAttributeType {
+        name: String,
+        expected: &'static str,
+    },
+    /// the mesh has no index buffer
+    MissingIndices,
+    /// the range reaches past the last vertex of the mesh
+    OutOfBounds {
+        vertices: Range,
+        vertex_count: usize,
+    },
+    /// the sub-mesh was removed from its batch, or never belonged to it
+    UnknownSubMesh(SubMeshId),
+    /// the operation needs at least one vertex
+    EmptyRange,
+    /// the mesh has more vertices than a chunk is allowed to hold
+    OverBudget { vertex_count: usize, budget: usize },
+    /// the mesh is too broken to work with, see `Meshie::repair`
+    Invalid(MeshIssue),
+    /// a value was given per vertex, but not for as many vertices as the range holds
+    WrongLength { expected: usize, found: usize },
+    /// a `MeshieBatch` was handed a mesh with a different number of vertices than it put in
+    BatchMismatch { expected: usize, found: usize },
+}
+
+impl fmt::Display for MeshieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshieError::MissingAttribute(name) => write!(f, "mesh has no {} attribute", name),
+            MeshieError::WrongAttributeType { name, expected } => {
+                write!(f, "mesh attribute {} is not {}", name, expected)
+            }
+            MeshieError::MissingIndices => write!(f, "mesh has no indices"),
+            MeshieError::OutOfBounds {
+                vertices,
+                vertex_count,
+            } => write!(
+                f,
+                "vertices {}..{} are out of bounds for a mesh of {} vertices",
+                vertices.start, vertices.end, vertex_count
+            ),
+            MeshieError::UnknownSubMesh(id) => write!(f, "no sub-mesh {:?} in batch", id),
+            MeshieError::EmptyRange => write!(f, "range has no vertices"),
+            MeshieError::OverBudget {
+                vertex_count,
+                budget,
+            } => write!(
+                f,
+                "mesh of {} vertices doesn't fit in a budget of {}",
+                vertex_count, budget
+            ),
+            MeshieError::Invalid(issue) => write!(f, "mesh is invalid: {:?}", issue),
+            MeshieError::WrongLength { expected, found } => write!(
+                f,
+                "expected a value for each of {} vertices, got {}",
+                expected, found
+            ),
+            MeshieError::BatchMismatch { expected, found } => write!(
+                f,
+                "batch expects a mesh of {} vertices, got {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MeshieError {}
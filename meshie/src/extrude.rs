@@ -0,0 +1,263 @@
+use std::collections::HashSet;
+
+use bevy::{math::Vec3, prelude::Mesh};
+use ds_range::Range;
+
+use crate::{attributes, indices_like, indices_to_u32, MeshieError};
+
+/// Pulls the triangles inside `vertices` out along `direction`.
+///
+/// The vertices are copied into a cap at the end of the mesh and the triangles are moved onto
+/// it. Every boundary edge of those triangles gets a side quad joining the old vertices to the
+/// cap, wound to match the faces it came from. When `direction` points against the faces, the
+/// cap and walls are wound the other way and the cap's normals turned round, so everything
+/// still faces out of the extruded block.
+///
+/// Side vertices copy the attributes of the vertex they were pulled from, except for the flat
+/// normal of their wall and their uvs, which run along the edge in u and up the wall in v. The
+/// uvs are in mesh units so a repeating texture keeps its scale from wall to wall. The old
+/// vertices are left in place, so neighbouring triangles outside the range stay connected to
+/// the bottom of the walls.
+pub(crate) fn extrude(
+    mesh: &mut Mesh,
+    vertices: Range,
+    direction: Vec3,
+) -> Result<Range, MeshieError> {
+    attributes::check_range(mesh, vertices)?;
+    let indices = mesh.indices.as_ref().ok_or(MeshieError::MissingIndices)?;
+    let mut values = indices_to_u32(indices);
+    let inside = |i: u32| i as usize >= vertices.start && (i as usize) < vertices.end;
+
+    // an edge is on the boundary when no other triangle in the region runs back along it
+    let mut edges = Vec::new();
+    for triangle in values.chunks_exact(3) {
+        if triangle.iter().all(|i| inside(*i)) {
+            edges.push((triangle[0], triangle[1]));
+            edges.push((triangle[1], triangle[2]));
+            edges.push((triangle[2], triangle[0]));
+        }
+    }
+    let edge_set: HashSet<(u32, u32)> = edges.iter().cloned().collect();
+    // pushed back through its own faces the region is wound inside out, so flip it
+    let positions = attributes::positions(mesh)?;
+    let corner = |i: u32| Vec3::from(positions[i as usize]);
+    let facing = values
+        .chunks_exact(3)
+        .filter(|triangle| triangle.iter().all(|i| inside(*i)))
+        .fold(Vec3::zero(), |facing, t| {
+            facing + (corner(t[1]) - corner(t[0])).cross(corner(t[2]) - corner(t[0]))
+        });
+    let backwards = direction.dot(facing) < 0.0;
+    let boundary: Vec<(u32, u32)> = edges
+        .into_iter()
+        .filter(|(a, b)| !edge_set.contains(&(*b, *a)))
+        .map(|(a, b)| if backwards { (b, a) } else { (a, b) })
+        .collect();
+
+    let count = attributes::vertex_count(mesh)?;
+    let cap = Range {
+        start: count,
+        end: count + vertices.len(),
+    };
+    let mut sources: Vec<usize> = vertices.iter().collect();
+    for (a, b) in boundary.iter() {
+        sources.extend_from_slice(&[*a as usize, *b as usize, *b as usize, *a as usize]);
+    }
+    for attribute in mesh.attributes.iter_mut() {
+        attributes::push_copies(&mut attribute.values, &sources);
+    }
+
+    let positions = attributes::positions_mut(mesh)?;
+    let moved = |p: [f32; 3]| {
+        [
+            p[0] + direction.x(),
+            p[1] + direction.y(),
+            p[2] + direction.z(),
+        ]
+    };
+    for i in cap.iter() {
+        positions[i] = moved(positions[i]);
+    }
+    let mut walls = Vec::with_capacity(boundary.len());
+    let mut widths = Vec::with_capacity(boundary.len());
+    for side in 0..boundary.len() {
+        let base = cap.end + side * 4;
+        positions[base + 2] = moved(positions[base + 2]);
+        positions[base + 3] = moved(positions[base + 3]);
+        let a = Vec3::from(positions[base]);
+        let b = Vec3::from(positions[base + 1]);
+        let top = Vec3::from(positions[base + 2]);
+        walls.push((b - a).cross(top - a));
+        widths.push((b - a).length());
+    }
+    if let Ok(normals) = attributes::normals_mut(mesh) {
+        if backwards {
+            for i in cap.iter() {
+                normals[i] = [-normals[i][0], -normals[i][1], -normals[i][2]];
+            }
+        }
+        for (side, wall) in walls.iter().enumerate() {
+            if wall.length_squared() > 0.0 {
+                let normal = wall.normalize();
+                for i in 0..4 {
+                    normals[cap.end + side * 4 + i] = [normal.x(), normal.y(), normal.z()];
+                }
+            }
+        }
+    }
+    if let Ok(uvs) = attributes::uvs_mut(mesh) {
+        let height = direction.length();
+        for (side, width) in widths.iter().enumerate() {
+            let base = cap.end + side * 4;
+            uvs[base] = [0.0, 0.0];
+            uvs[base + 1] = [*width, 0.0];
+            uvs[base + 2] = [*width, height];
+            uvs[base + 3] = [0.0, height];
+        }
+    }
+
+    // only triangles wholly inside the range belong to the region
+    for triangle in values.chunks_exact_mut(3) {
+        if triangle.iter().all(|i| inside(*i)) {
+            for i in triangle.iter_mut() {
+                *i = (cap.start + (*i as usize - vertices.start)) as u32;
+            }
+            if backwards {
+                triangle.swap(0, 2);
+            }
+        }
+    }
+    for side in 0..boundary.len() {
+        let base = (cap.end + side * 4) as u32;
+        values.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+    let vertex_count = attributes::vertex_count(mesh)?;
+    mesh.indices = Some(indices_like(
+        mesh.indices.as_ref().expect("indices were checked above"),
+        values,
+        vertex_count,
+    ));
+    Ok(cap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Meshie;
+    use bevy::{
+        math::{vec2, vec3},
+        prelude::shape,
+        render::mesh::Indices,
+    };
+
+    fn quad() -> Mesh {
+        Mesh::from(shape::Quad {
+            size: vec2(2., 2.),
+            flip: false,
+        })
+    }
+    fn triangles(mesh: &Mesh) -> Vec<[Vec3; 3]> {
+        let positions = attributes::positions(mesh).unwrap();
+        indices_to_u32(mesh.indices.as_ref().unwrap())
+            .chunks_exact(3)
+            .map(|t| {
+                [
+                    Vec3::from(positions[t[0] as usize]),
+                    Vec3::from(positions[t[1] as usize]),
+                    Vec3::from(positions[t[2] as usize]),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn extruded_quad_faces_outward() {
+        let mut mesh = quad();
+        let cap = mesh.extrude_mesh(
+            attributes::vertex_range(&mesh).unwrap(),
+            vec3(0.0, 0.0, 3.0),
+        );
+        let cap = cap.unwrap();
+        assert_eq!(cap, Range { start: 4, end: 8 });
+        // 4 original, 4 on the cap and 4 for each of the 4 walls
+        assert_eq!(attributes::vertex_count(&mesh), Ok(24));
+        assert_eq!(
+            indices_to_u32(mesh.indices.as_ref().unwrap()).len(),
+            6 + 4 * 6
+        );
+        for position in mesh.get_positions(cap).unwrap() {
+            assert_eq!(position[2], 3.0);
+        }
+        assert_eq!(
+            attributes::uvs(&mesh).unwrap()[cap.iter()].to_vec(),
+            attributes::uvs(&quad()).unwrap().clone()
+        );
+
+        assert_faces_outward(&mesh, vec3(0.0, 0.0, 1.5));
+
+        // every wall gets the whole of its edge in u and its height in v
+        let uvs = attributes::uvs(&mesh).unwrap();
+        for wall in uvs[cap.end..].chunks_exact(4) {
+            assert_eq!(wall, &[[0.0, 0.0], [2.0, 0.0], [2.0, 3.0], [0.0, 3.0]]);
+        }
+    }
+
+    #[test]
+    fn extruding_against_the_faces_still_faces_outward() {
+        let mut mesh = quad();
+        let cap = mesh
+            .extrude_mesh(Range { start: 0, end: 4 }, vec3(0.0, 0.0, -3.0))
+            .unwrap();
+        for position in mesh.get_positions(cap).unwrap() {
+            assert_eq!(position[2], -3.0);
+        }
+        assert_faces_outward(&mesh, vec3(0.0, 0.0, -1.5));
+    }
+
+    /// checks every triangle faces away from `center` and its normals match its face
+    fn assert_faces_outward(mesh: &Mesh, center: Vec3) {
+        let normals = attributes::normals(mesh).unwrap();
+        for (t, triangle) in triangles(mesh).iter().enumerate() {
+            let face = (triangle[1] - triangle[0]).cross(triangle[2] - triangle[0]);
+            let centroid = (triangle[0] + triangle[1] + triangle[2]) / 3.0;
+            assert!(
+                face.dot(centroid - center) > 0.0,
+                "triangle {} faces inward",
+                t
+            );
+            let index = indices_to_u32(mesh.indices.as_ref().unwrap())[t * 3] as usize;
+            assert!((Vec3::from(normals[index]) - face.normalize()).length() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn extrusions_chain() {
+        let mut mesh = quad();
+        let first = mesh.add_mesh(&quad()).unwrap();
+        mesh.translate_mesh(first, vec3(10.0, 0.0, 0.0)).unwrap();
+        let cap = mesh.extrude_mesh(first, vec3(0.0, 0.0, 1.0)).unwrap();
+        let cap = mesh.extrude_mesh(cap, vec3(0.0, 0.0, 1.0)).unwrap();
+        assert_eq!(cap, Range { start: 28, end: 32 });
+        assert_eq!(attributes::vertex_count(&mesh), Ok(48));
+        assert_eq!(mesh.get_center(cap).unwrap(), vec3(10.0, 0.0, 2.0));
+        // the untouched quad and the cap are the only flat faces left
+        let flat: Vec<_> = triangles(&mesh)
+            .into_iter()
+            .filter(|t| t[0].z() == t[1].z() && t[1].z() == t[2].z())
+            .map(|t| t[0].z())
+            .collect();
+        assert_eq!(flat, vec![0.0, 0.0, 2.0, 2.0]);
+        assert!(matches!(mesh.indices, Some(Indices::U32(_))));
+    }
+
+    #[test]
+    fn extrude_keeps_u16_indices() {
+        let mut mesh = quad();
+        if let Some(Indices::U32(values)) = mesh.indices.take() {
+            mesh.indices = Some(Indices::U16(values.iter().map(|i| *i as u16).collect()));
+        }
+        mesh.extrude_mesh(Range { start: 0, end: 4 }, vec3(0.0, 0.0, 1.0))
+            .unwrap();
+        assert!(matches!(mesh.indices, Some(Indices::U16(_))));
+    }
+}
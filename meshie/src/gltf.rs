@@ -0,0 +1,419 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use bevy::{
+    math::{Mat4, Vec3},
+    prelude::Mesh,
+    render::{
+        mesh::{Indices, VertexAttribute, VertexAttributeValues},
+        pipeline::PrimitiveTopology,
+    },
+};
+use serde_json::{json, Value};
+
+use crate::{
+    attributes::{self, COLOR, TANGENT},
+    Aabb, MeshIssue, MeshieError,
+};
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// A mesh to export along with where it sits in the scene and the image it's textured with.
+pub struct GltfMesh<'a> {
+    pub name: &'a str,
+    pub mesh: &'a Mesh,
+    pub transform: Mat4,
+    /// uri of the texture, relative to the `.gltf` file
+    pub texture: Option<&'a str>,
+}
+
+#[derive(Debug)]
+pub enum GltfError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Mesh(MeshieError),
+    /// the named mesh has no vertices or no indices, which glTF has no way to write
+    EmptyMesh(String),
+}
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GltfError::Io(error) => write!(f, "gltf io error: {}", error),
+            GltfError::Json(error) => write!(f, "gltf json error: {}", error),
+            GltfError::Mesh(error) => write!(f, "{}", error),
+            GltfError::EmptyMesh(name) => write!(f, "gltf mesh {} is empty", name),
+        }
+    }
+}
+
+impl std::error::Error for GltfError {}
+
+impl From<io::Error> for GltfError {
+    fn from(error: io::Error) -> Self {
+        GltfError::Io(error)
+    }
+}
+impl From<serde_json::Error> for GltfError {
+    fn from(error: serde_json::Error) -> Self {
+        GltfError::Json(error)
+    }
+}
+impl From<MeshieError> for GltfError {
+    fn from(error: MeshieError) -> Self {
+        GltfError::Mesh(error)
+    }
+}
+
+/// Writes the meshes to `path` as a `.gltf` document, with their vertex data in a `.bin` of the
+/// same name next to it. Each mesh becomes a node of the one scene.
+pub fn save_gltf(path: impl AsRef<Path>, meshes: &[GltfMesh]) -> Result<(), GltfError> {
+    let path = path.as_ref();
+    let bin_path = path.with_extension("bin");
+    let bin_uri = bin_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (document, bin) = to_gltf(meshes, &bin_uri)?;
+    fs::write(&bin_path, bin)?;
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, &document)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Builds the glTF document and the contents of the buffer it points at through `bin_uri`.
+///
+/// The standard attributes map to their glTF names (`Vertex_Color` becomes `COLOR_0`), any
+/// others are kept with a leading underscore as the spec asks of custom attributes. Meshes
+/// sharing a texture share a material. glTF buffers can't be empty, so a mesh without
+/// vertices, or with an index buffer holding none, is an error.
+pub fn to_gltf(meshes: &[GltfMesh], bin_uri: &str) -> Result<(Value, Vec<u8>), GltfError> {
+    let mut bin = Vec::new();
+    let mut views = Vec::new();
+    let mut accessors = Vec::new();
+    let mut gltf_meshes = Vec::new();
+    let mut nodes = Vec::new();
+    let mut images = Vec::new();
+    let mut materials = Vec::new();
+    let mut material_for_texture = HashMap::new();
+
+    for (m, gltf_mesh) in meshes.iter().enumerate() {
+        let mesh = gltf_mesh.mesh;
+        let mode = match mesh.primitive_topology {
+            PrimitiveTopology::PointList => 0,
+            PrimitiveTopology::LineList => 1,
+            PrimitiveTopology::LineStrip => 3,
+            PrimitiveTopology::TriangleList => 4,
+            PrimitiveTopology::TriangleStrip => 5,
+        };
+        let vertex_count = attributes::vertex_count(mesh)?;
+        let no_indices = match mesh.indices.as_ref() {
+            Some(Indices::U16(values)) => values.is_empty(),
+            Some(Indices::U32(values)) => values.is_empty(),
+            None => false,
+        };
+        if vertex_count == 0 || no_indices {
+            return Err(GltfError::EmptyMesh(gltf_mesh.name.to_string()));
+        }
+
+        let mut primitive_attributes = serde_json::Map::new();
+        for attribute in mesh.attributes.iter() {
+            if attribute.values.len() != vertex_count {
+                return Err(MeshieError::Invalid(MeshIssue::AttributeLength {
+                    name: attribute.name.to_string(),
+                    len: attribute.values.len(),
+                })
+                .into());
+            }
+            let (kind, floats): (&str, Vec<f32>) = match &attribute.values {
+                VertexAttributeValues::Float(values) => ("SCALAR", values.clone()),
+                VertexAttributeValues::Float2(values) => {
+                    ("VEC2", values.iter().flatten().cloned().collect())
+                }
+                VertexAttributeValues::Float3(values) => {
+                    ("VEC3", values.iter().flatten().cloned().collect())
+                }
+                VertexAttributeValues::Float4(values) => {
+                    ("VEC4", values.iter().flatten().cloned().collect())
+                }
+            };
+            let mut bytes = Vec::with_capacity(floats.len() * 4);
+            for value in floats {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            let view = push_view(&mut bin, &mut views, &bytes, ARRAY_BUFFER);
+            let mut accessor = json!({
+                "bufferView": view,
+                "componentType": FLOAT,
+                "count": vertex_count,
+                "type": kind,
+            });
+            if attribute.name == VertexAttribute::POSITION {
+                // required for positions, and lets viewers frame the scene
+                if let Some(aabb) =
+                    Aabb::from_points(attributes::positions(mesh)?.iter().map(|p| Vec3::from(*p)))
+                {
+                    accessor["min"] = json!([aabb.min.x(), aabb.min.y(), aabb.min.z()]);
+                    accessor["max"] = json!([aabb.max.x(), aabb.max.y(), aabb.max.z()]);
+                }
+            }
+            accessors.push(accessor);
+            primitive_attributes.insert(
+                gltf_attribute_name(&attribute.name),
+                json!(accessors.len() - 1),
+            );
+        }
+
+        let mut primitive = json!({
+            "attributes": primitive_attributes,
+            "mode": mode,
+        });
+        if let Some(indices) = mesh.indices.as_ref() {
+            let (component_type, count, bytes) = match indices {
+                Indices::U16(values) => (
+                    UNSIGNED_SHORT,
+                    values.len(),
+                    values
+                        .iter()
+                        .flat_map(|i| i.to_le_bytes().to_vec())
+                        .collect::<Vec<_>>(),
+                ),
+                Indices::U32(values) => (
+                    UNSIGNED_INT,
+                    values.len(),
+                    values
+                        .iter()
+                        .flat_map(|i| i.to_le_bytes().to_vec())
+                        .collect(),
+                ),
+            };
+            let view = push_view(&mut bin, &mut views, &bytes, ELEMENT_ARRAY_BUFFER);
+            accessors.push(json!({
+                "bufferView": view,
+                "componentType": component_type,
+                "count": count,
+                "type": "SCALAR",
+            }));
+            primitive["indices"] = json!(accessors.len() - 1);
+        }
+        if let Some(texture) = gltf_mesh.texture {
+            let material = *material_for_texture.entry(texture).or_insert_with(|| {
+                images.push(json!({ "uri": texture }));
+                materials.push(json!({
+                    "name": texture,
+                    "pbrMetallicRoughness": {
+                        "baseColorTexture": { "index": images.len() - 1 },
+                        "metallicFactor": 0.0,
+                    },
+                }));
+                materials.len() - 1
+            });
+            primitive["material"] = json!(material);
+        }
+
+        gltf_meshes.push(json!({
+            "name": gltf_mesh.name,
+            "primitives": [primitive],
+        }));
+        let mut node = json!({
+            "name": gltf_mesh.name,
+            "mesh": m,
+        });
+        if gltf_mesh.transform != Mat4::identity() {
+            node["matrix"] = json!(gltf_mesh.transform.to_cols_array().to_vec());
+        }
+        nodes.push(node);
+    }
+
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "meshie" },
+        "scene": 0,
+        "scenes": [{ "nodes": (0..nodes.len()).collect::<Vec<_>>() }],
+        "nodes": nodes,
+        "meshes": gltf_meshes,
+        "accessors": accessors,
+        "bufferViews": views,
+        "buffers": [{ "uri": bin_uri, "byteLength": bin.len() }],
+    });
+    if !images.is_empty() {
+        // one texture per image, all sampled the default way
+        let textures: Vec<Value> = (0..images.len())
+            .map(|i| json!({ "source": i, "sampler": 0 }))
+            .collect();
+        document["samplers"] = json!([{}]);
+        document["images"] = json!(images);
+        document["textures"] = json!(textures);
+        document["materials"] = json!(materials);
+    }
+    Ok((document, bin))
+}
+
+/// appends `bytes` to the buffer as a new view, starting on a four byte boundary
+fn push_view(bin: &mut Vec<u8>, views: &mut Vec<Value>, bytes: &[u8], target: u32) -> usize {
+    while bin.len() % 4 != 0 {
+        bin.push(0);
+    }
+    views.push(json!({
+        "buffer": 0,
+        "byteOffset": bin.len(),
+        "byteLength": bytes.len(),
+        "target": target,
+    }));
+    bin.extend_from_slice(bytes);
+    views.len() - 1
+}
+
+fn gltf_attribute_name(name: &str) -> String {
+    match name {
+        VertexAttribute::POSITION => "POSITION".to_string(),
+        VertexAttribute::NORMAL => "NORMAL".to_string(),
+        VertexAttribute::UV => "TEXCOORD_0".to_string(),
+        COLOR => "COLOR_0".to_string(),
+        TANGENT => "TANGENT".to_string(),
+        other => format!("_{}", other.to_uppercase()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Meshie;
+    use bevy::{
+        math::{vec2, vec3, Quat},
+        prelude::shape,
+    };
+    use ds_range::Range;
+
+    fn quad() -> Mesh {
+        Mesh::from(shape::Quad {
+            size: vec2(2., 2.),
+            flip: false,
+        })
+    }
+
+    #[test]
+    fn document_points_at_the_buffer() {
+        let mut stars = quad();
+        stars.indices = Some(Indices::U16(vec![0, 2, 1, 0, 3, 2]));
+        stars
+            .set_colors(Range { start: 0, end: 4 }, [1.0, 0.5, 0.5, 1.0])
+            .unwrap();
+        let ship = Mesh::from(shape::Cube { size: 1.0 });
+        let moved =
+            Mat4::from_rotation_translation(Quat::from_rotation_z(1.0), vec3(5.0, 0.0, 0.0));
+        let (document, bin) = to_gltf(
+            &[
+                GltfMesh {
+                    name: "stars",
+                    mesh: &stars,
+                    transform: Mat4::identity(),
+                    texture: Some("atlas.png"),
+                },
+                GltfMesh {
+                    name: "ship",
+                    mesh: &ship,
+                    transform: moved,
+                    texture: Some("atlas.png"),
+                },
+            ],
+            "sector.bin",
+        )
+        .unwrap();
+
+        assert_eq!(document["asset"]["version"], "2.0");
+        assert_eq!(document["buffers"][0]["byteLength"], bin.len());
+        assert_eq!(document["scenes"][0]["nodes"], json!([0, 1]));
+        assert!(document["nodes"][0].get("matrix").is_none());
+        assert_eq!(document["nodes"][1]["matrix"][12], 5.0);
+        assert_eq!(document["materials"].as_array().unwrap().len(), 1);
+        assert_eq!(document["images"][0]["uri"], "atlas.png");
+
+        let stars = &document["meshes"][0]["primitives"][0];
+        assert_eq!(stars["mode"], 4);
+        assert_eq!(stars["material"], 0);
+        let position =
+            &document["accessors"][stars["attributes"]["POSITION"].as_u64().unwrap() as usize];
+        assert_eq!(position["min"], json!([-1.0, -1.0, 0.0]));
+        assert_eq!(position["max"], json!([1.0, 1.0, 0.0]));
+        let color =
+            &document["accessors"][stars["attributes"]["COLOR_0"].as_u64().unwrap() as usize];
+        assert_eq!(color["type"], "VEC4");
+        let indices = &document["accessors"][stars["indices"].as_u64().unwrap() as usize];
+        assert_eq!(indices["componentType"], UNSIGNED_SHORT);
+
+        // every view starts aligned and stays inside the buffer
+        for view in document["bufferViews"].as_array().unwrap() {
+            let offset = view["byteOffset"].as_u64().unwrap() as usize;
+            let length = view["byteLength"].as_u64().unwrap() as usize;
+            assert_eq!(offset % 4, 0);
+            assert!(offset + length <= bin.len());
+        }
+        // the ship's indices are the last thing in the buffer
+        let last = bin.len() - 4;
+        assert_eq!(
+            u32::from_le_bytes([bin[last], bin[last + 1], bin[last + 2], bin[last + 3]]),
+            match ship.indices.as_ref().unwrap() {
+                Indices::U32(values) => *values.last().unwrap(),
+                Indices::U16(values) => *values.last().unwrap() as u32,
+            }
+        );
+    }
+
+    #[test]
+    fn empty_meshes_are_refused() {
+        let mut no_triangles = quad();
+        no_triangles.indices = Some(Indices::U32(Vec::new()));
+        let empty = Mesh::new(PrimitiveTopology::TriangleList);
+        let mut no_vertices = quad();
+        no_vertices.remove_mesh(Range { start: 0, end: 4 }).unwrap();
+        for (name, mesh) in [
+            ("no triangles", &no_triangles),
+            ("no vertices", &no_vertices),
+        ]
+        .iter()
+        {
+            let error = to_gltf(
+                &[GltfMesh {
+                    name,
+                    mesh,
+                    transform: Mat4::identity(),
+                    texture: None,
+                }],
+                "empty.bin",
+            )
+            .unwrap_err();
+            assert!(matches!(error, GltfError::EmptyMesh(ref n) if n == name));
+        }
+        // a mesh without positions at all never gets that far
+        let error = to_gltf(
+            &[GltfMesh {
+                name: "nothing",
+                mesh: &empty,
+                transform: Mat4::identity(),
+                texture: None,
+            }],
+            "empty.bin",
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            GltfError::Mesh(MeshieError::MissingAttribute(_))
+        ));
+    }
+
+    #[test]
+    fn custom_attributes_get_an_underscore() {
+        assert_eq!(gltf_attribute_name("Vertex_Heat"), "_VERTEX_HEAT");
+        assert_eq!(gltf_attribute_name(VertexAttribute::UV), "TEXCOORD_0");
+    }
+}
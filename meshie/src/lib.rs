@@ -1,219 +1,487 @@
 use ds_range::Range;
 
-use bevy::{math::Quat, math::Vec2, math::Vec3, prelude::Mesh, render::mesh::Indices};
+use bevy::{
+    math::Mat4,
+    math::Quat,
+    math::Vec3,
+    prelude::Mesh,
+    render::mesh::{Indices, VertexAttribute, VertexAttributeValues},
+};
 
+pub mod atlas;
+pub mod attributes;
+pub mod batch;
+pub mod bounds;
+pub mod cache;
+pub mod error;
+mod extrude;
 pub mod generator;
+pub mod gltf;
+mod normals;
+pub mod obj;
+pub mod remap;
+pub mod split;
+pub mod types;
+pub mod validate;
+mod weld;
 
+pub use batch::{MeshieBatch, SubMeshId};
+pub use bounds::{Aabb, BoundingSphere};
+pub use error::MeshieError;
+pub use remap::Remap;
+pub use split::{split_mesh, Placement, Splitter};
+pub use types::{Adjacency, EditMesh, Triangle, Vertex};
+pub use validate::{MeshIssue, ValidationReport};
+
+use attributes::{
+    check_length, check_values, ensure_colors, normals_mut, positions, positions_mut, uvs_mut,
+    COLOR,
+};
+
+/// Editing helpers for meshes built up out of other meshes. Attributes are looked up by
+/// name, so meshes can carry them in any order alongside extra attributes.
 pub trait Meshie {
     fn reverse_triangles(&mut self);
-    fn add_mesh(&mut self, other: &Self) -> Range;
-    fn translate_mesh(&mut self, vertices: ds_range::Range, translation: Vec3);
-    fn rotate_mesh(&mut self, vertices: ds_range::Range, rotation: Quat);
-    fn rotate_from_meshie_center(&mut self, vertices: ds_range::Range, rotation: Quat);
-    fn get_center(&self, vertices: ds_range::Range) -> Vec3;
-    fn extend_mesh(&mut self, vertices: ds_range::Range, direction: Vec3);
-    fn set_uvs(&mut self, vertices: Range, uv: Vec<[f32;2]>);
-    fn get_positions(&self, vertices: ds_range::Range) -> Vec<[f32; 3]>;
-    fn set_positions(&mut self, vertices: ds_range::Range, positions: Vec<[f32; 3]>);
+    fn add_mesh(&mut self, other: &Self) -> Result<Range, MeshieError>;
+    fn remove_mesh(&mut self, vertices: Range) -> Result<Remap, MeshieError>;
+    fn translate_mesh(&mut self, vertices: Range, translation: Vec3) -> Result<(), MeshieError>;
+    fn rotate_mesh(&mut self, vertices: Range, rotation: Quat) -> Result<(), MeshieError>;
+    fn rotate_from_meshie_center(
+        &mut self,
+        vertices: Range,
+        rotation: Quat,
+    ) -> Result<(), MeshieError>;
+    fn scale_mesh(&mut self, vertices: Range, scale: Vec3) -> Result<(), MeshieError>;
+    fn scale_mesh_around(
+        &mut self,
+        vertices: Range,
+        scale: Vec3,
+        pivot: Vec3,
+    ) -> Result<(), MeshieError>;
+    fn transform_mesh(&mut self, vertices: Range, transform: Mat4) -> Result<(), MeshieError>;
+    fn compute_flat_normals(&mut self, vertices: Range) -> Result<(), MeshieError>;
+    fn compute_smooth_normals(&mut self, vertices: Range) -> Result<(), MeshieError>;
+    fn compute_tangents(&mut self, vertices: Range) -> Result<(), MeshieError>;
+    fn get_center(&self, vertices: Range) -> Result<Vec3, MeshieError>;
+    fn aabb(&self, vertices: Range) -> Result<Aabb, MeshieError>;
+    fn bounding_sphere(&self, vertices: Range) -> Result<BoundingSphere, MeshieError>;
+    fn extend_mesh(&mut self, vertices: Range, direction: Vec3) -> Result<(), MeshieError>;
+    fn extrude_mesh(&mut self, vertices: Range, direction: Vec3) -> Result<Range, MeshieError>;
+    fn set_uvs(&mut self, vertices: Range, uv: Vec<[f32; 2]>) -> Result<(), MeshieError>;
+    fn set_colors(&mut self, vertices: Range, color: [f32; 4]) -> Result<(), MeshieError>;
+    fn get_positions(&self, vertices: Range) -> Result<Vec<[f32; 3]>, MeshieError>;
+    fn set_positions(
+        &mut self,
+        vertices: Range,
+        positions: Vec<[f32; 3]>,
+    ) -> Result<(), MeshieError>;
+    fn weld(&mut self, vertices: Range, epsilon: f32) -> Result<usize, MeshieError>;
+    fn validate(&self) -> Result<ValidationReport, MeshieError>;
+    fn repair(&mut self) -> Result<usize, MeshieError>;
 }
 
 impl Meshie for Mesh {
     fn reverse_triangles(&mut self) {
         if let Some(i) = self.indices.as_mut() {
             match i {
-                Indices::U16(_) => {}
-                Indices::U32(ref mut ind) => {
-                    for triangle in ind.rchunks_exact_mut(3) {
-                        let t0 = triangle[0];
-                        triangle[0] = triangle[2];
-                        triangle[2] = t0;
-                    }
-                }
+                Indices::U16(ref mut ind) => reverse_winding(ind),
+                Indices::U32(ref mut ind) => reverse_winding(ind),
             }
         }
     }
-    fn add_mesh(&mut self, other: &Mesh) -> Range {
-        let mut result: Range = Range::default();
-        if let Some(indices) = self.indices.as_mut() {
-            match indices {
-                Indices::U16(_) => {}
-                Indices::U32(ref mut values) => {
-                    match other.indices.as_ref().expect("other indices") {
-                        Indices::U16(_) => {}
-                        Indices::U32(ref addons) => {
-                            add_indices(values, addons, self.attributes[0].values.len());
-                        }
-                    }
-                }
-            }
-        } else {
-            match other.indices.as_ref().expect("other indices") {
-                Indices::U16(_) => {}
-                Indices::U32(ref addons) => {
-                    add_indices(&mut vec![], addons, 0);
-                }
+    /// appends every attribute of `other` that matches one on `self` by name. `self` is left
+    /// untouched if `other` is missing one of its attributes. a mesh without any attributes
+    /// takes on the attributes of `other`, and vertex colors are padded out with white when
+    /// only one of the meshes has them.
+    fn add_mesh(&mut self, other: &Mesh) -> Result<Range, MeshieError> {
+        let other_indices = other.indices.as_ref().ok_or(MeshieError::MissingIndices)?;
+        if self.attributes.is_empty() {
+            positions(other)?;
+            for attribute in other.attributes.iter() {
+                self.attributes.push(VertexAttribute {
+                    name: attribute.name.clone(),
+                    values: attributes::empty_like(&attribute.values),
+                });
             }
         }
-        match self.attributes[0].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref mut values) => {
-                match other.attributes[0].values {
-                    bevy::render::mesh::VertexAttributeValues::Float3(ref addons) => {
-                        result.start = values.len();
-                        result.end = values.len() + addons.len();
-                        add_positions(values, addons);
-                    }
-                    _ => panic!(" no positions on mesh"),
-                }
+        let count = positions(self)?.len();
+        let other_count = positions(other)?.len();
+        for attribute in self.attributes.iter() {
+            let addons = match attributes::attribute(other, &attribute.name) {
+                Err(MeshieError::MissingAttribute(_)) if attribute.name == COLOR => continue,
+                addons => addons?,
+            };
+            if !attributes::same_type(&attribute.values, addons) {
+                return Err(MeshieError::WrongAttributeType {
+                    name: attribute.name.to_string(),
+                    expected: attributes::type_name(&attribute.values),
+                });
             }
-            _ => {}
         }
-        match self.attributes[1].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref mut values) => {
-                match other.attributes[1].values {
-                    bevy::render::mesh::VertexAttributeValues::Float3(ref addons) => {
-                        add_normals(values, addons);
-                    }
-                    _ => {}
-                }
-            }
-            _ => {}
+        if attributes::colors(other).is_ok() {
+            ensure_colors(self)?;
         }
-        match self.attributes[2].values {
-            bevy::render::mesh::VertexAttributeValues::Float2(ref mut values) => {
-                match other.attributes[2].values {
-                    bevy::render::mesh::VertexAttributeValues::Float2(ref addons) => {
-                        add_uvs(values, addons);
-                    }
-                    _ => {}
+
+        self.indices = Some(merge_indices(
+            self.indices.take(),
+            other_indices,
+            count,
+            count + other_count,
+        ));
+        for attribute in self.attributes.iter_mut() {
+            match attributes::attribute(other, &attribute.name) {
+                Ok(addons) => {
+                    attributes::append_values(&attribute.name, &mut attribute.values, addons)?
                 }
+                Err(_) => attributes::append_values(
+                    &attribute.name,
+                    &mut attribute.values,
+                    &VertexAttributeValues::Float4(vec![attributes::WHITE; other_count]),
+                )?,
             }
-            _ => {}
         }
-        result
+        // only checks what was appended, and only in debug builds. it never changes what
+        // add_mesh returns
+        let merged = self.indices.as_ref().map_or(0, index_count);
+        let first_triangle = (merged - index_count(other_indices)) / 3;
+        debug_assert!(
+            validate::validate_from(self, count, first_triangle).map_or(false, |r| r.is_valid()),
+            "add_mesh broke the mesh"
+        );
+        Ok(Range {
+            start: count,
+            end: count + other_count,
+        })
     }
 
-    fn translate_mesh(&mut self, vertices: ds_range::Range, translation: Vec3) {
-        match self.attributes[0].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref mut values) => {
-                for i in vertices.iter() {
-                    values[i][0] = values[i][0] + translation.x();
-                    values[i][1] = values[i][1] + translation.y();
-                    values[i][2] = values[i][2] + translation.z();
+    /// removes the vertices in the range along with every triangle that uses them. indices
+    /// after the range are shifted down to match.
+    fn remove_mesh(&mut self, vertices: Range) -> Result<Remap, MeshieError> {
+        attributes::check_range(self, vertices)?;
+        let remap = Remap { removed: vertices };
+        if let Some(indices) = self.indices.as_mut() {
+            match indices {
+                Indices::U16(ref mut values) => {
+                    *values =
+                        remove_triangles(values, |i| remap.vertex(i as usize).map(|i| i as u16))
+                }
+                Indices::U32(ref mut values) => {
+                    *values =
+                        remove_triangles(values, |i| remap.vertex(i as usize).map(|i| i as u32))
                 }
             }
-            _ => {}
         }
+        for attribute in self.attributes.iter_mut() {
+            attributes::remove_values(&mut attribute.values, vertices);
+        }
+        Ok(remap)
     }
-    fn rotate_mesh(&mut self, vertices: ds_range::Range, rotation: Quat) {
-        let center = self.get_center(vertices);
-        match self.attributes[0].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref mut values) => {
-                for i in vertices.iter() {
-                    let new_pos =
-                        rotation.mul_vec3(Vec3::from_slice_unaligned(&values[i]) - center) + center;
-                    values[i] = [new_pos.x(), new_pos.y(), new_pos.z()];
-                }
-            }
-            _ => {}
+
+    fn translate_mesh(&mut self, vertices: Range, translation: Vec3) -> Result<(), MeshieError> {
+        let values = positions_mut(self)?;
+        check_values(values, vertices)?;
+        for i in vertices.iter() {
+            values[i][0] = values[i][0] + translation.x();
+            values[i][1] = values[i][1] + translation.y();
+            values[i][2] = values[i][2] + translation.z();
         }
+        Ok(())
     }
-    fn rotate_from_meshie_center(&mut self, vertices: ds_range::Range, rotation: Quat) {
-        // let center = self.get_center(vertices);
-        match self.attributes[0].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref mut values) => {
-                for i in vertices.iter() {
-                    let new_pos = rotation.mul_vec3(Vec3::from_slice_unaligned(&values[i]));
-                    values[i] = [new_pos.x(), new_pos.y(), new_pos.z()];
-                }
-            }
-            _ => {}
+    fn rotate_mesh(&mut self, vertices: Range, rotation: Quat) -> Result<(), MeshieError> {
+        let center = self.get_center(vertices)?;
+        let values = positions_mut(self)?;
+        for i in vertices.iter() {
+            let new_pos =
+                rotation.mul_vec3(Vec3::from_slice_unaligned(&values[i]) - center) + center;
+            values[i] = [new_pos.x(), new_pos.y(), new_pos.z()];
+        }
+        Ok(())
+    }
+    fn rotate_from_meshie_center(
+        &mut self,
+        vertices: Range,
+        rotation: Quat,
+    ) -> Result<(), MeshieError> {
+        let values = positions_mut(self)?;
+        check_values(values, vertices)?;
+        for i in vertices.iter() {
+            let new_pos = rotation.mul_vec3(Vec3::from_slice_unaligned(&values[i]));
+            values[i] = [new_pos.x(), new_pos.y(), new_pos.z()];
         }
+        Ok(())
     }
-    fn get_center(&self, vertices: ds_range::Range) -> Vec3 {
-        match self.attributes[0].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref values) => {
-                let mut average = Vec3::default();
+    /// scales the vertices around their center
+    fn scale_mesh(&mut self, vertices: Range, scale: Vec3) -> Result<(), MeshieError> {
+        let center = self.get_center(vertices)?;
+        self.scale_mesh_around(vertices, scale, center)
+    }
+    fn scale_mesh_around(
+        &mut self,
+        vertices: Range,
+        scale: Vec3,
+        pivot: Vec3,
+    ) -> Result<(), MeshieError> {
+        self.transform_mesh(
+            vertices,
+            Mat4::from_translation(pivot)
+                * Mat4::from_scale(scale)
+                * Mat4::from_translation(-pivot),
+        )
+    }
+    /// applies `transform` to the positions of the vertices and its inverse-transpose to their
+    /// normals, if the mesh has any. mirroring transforms also flip the winding of every
+    /// triangle inside the range so it keeps facing outwards.
+    fn transform_mesh(&mut self, vertices: Range, transform: Mat4) -> Result<(), MeshieError> {
+        let values = positions_mut(self)?;
+        check_values(values, vertices)?;
+        for i in vertices.iter() {
+            let new_pos = transform.transform_point3(Vec3::from_slice_unaligned(&values[i]));
+            values[i] = [new_pos.x(), new_pos.y(), new_pos.z()];
+        }
+        match normals_mut(self) {
+            Ok(values) => {
+                check_values(values, vertices)?;
+                let normal_transform = transform.inverse().transpose();
                 for i in vertices.iter() {
-                    average += Vec3::from_slice_unaligned(&values[i]);
+                    let normal =
+                        normal_transform.transform_vector3(Vec3::from_slice_unaligned(&values[i]));
+                    if normal.length_squared() > 0.0 {
+                        let normal = normal.normalize();
+                        values[i] = [normal.x(), normal.y(), normal.z()];
+                    }
                 }
-                average /= vertices.len() as f32;
-                average
             }
-            _ => panic!("Vertices are in the wrong order"),
+            Err(MeshieError::MissingAttribute(_)) => {}
+            Err(err) => return Err(err),
         }
-    }
-    fn extend_mesh(&mut self, vertices: ds_range::Range, direction: Vec3) {
-        let center = self.get_center(vertices);
-        match self.attributes[0].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref mut values) => {
-                for i in vertices.iter() {
-                    let vert = Vec3::from_slice_unaligned(&values[i]);
-                    if direction.dot(vert - center) > 0.0 {
-                        let new_pos = vert + direction;
-                        values[i] = [new_pos.x(), new_pos.y(), new_pos.z()];
+        if transform.determinant() < 0.0 {
+            if let Some(indices) = self.indices.as_mut() {
+                match indices {
+                    Indices::U16(ref mut ind) => {
+                        reverse_winding_in(ind, |i| vertices.iter().contains(&(i as usize)))
+                    }
+                    Indices::U32(ref mut ind) => {
+                        reverse_winding_in(ind, |i| vertices.iter().contains(&(i as usize)))
                     }
                 }
             }
-            _ => {}
         }
+        Ok(())
     }
-    fn set_uvs(&mut self, vertices: Range, uv: Vec<[f32;2]>) {
-        match self.attributes[2].values {
-            bevy::render::mesh::VertexAttributeValues::Float2(ref mut values) => {
-                for i in vertices.iter() {
-                    values[i] = uv[i - vertices.start];
-                }
+    /// gives each vertex the normal of the triangles that use it, for meshes where faces
+    /// don't share vertices this is flat shading. only triangles entirely inside the range
+    /// are used, pass `attributes::vertex_range` to cover the whole mesh.
+    fn compute_flat_normals(&mut self, vertices: Range) -> Result<(), MeshieError> {
+        normals::compute_normals(self, vertices, false)
+    }
+    /// like `compute_flat_normals`, but also averages across vertices at the same position so
+    /// seams between faces are smoothed over
+    fn compute_smooth_normals(&mut self, vertices: Range) -> Result<(), MeshieError> {
+        normals::compute_normals(self, vertices, true)
+    }
+    /// fills `Vertex_Tangent` for the range, adding the attribute if the mesh has none
+    fn compute_tangents(&mut self, vertices: Range) -> Result<(), MeshieError> {
+        normals::compute_tangents(self, vertices)
+    }
+    /// the average position of the vertices
+    fn get_center(&self, vertices: Range) -> Result<Vec3, MeshieError> {
+        let values = positions(self)?;
+        check_values(values, vertices)?;
+        if vertices.is_empty() {
+            return Err(MeshieError::EmptyRange);
+        }
+        let mut average = Vec3::default();
+        for i in vertices.iter() {
+            average += Vec3::from_slice_unaligned(&values[i]);
+        }
+        average /= vertices.len() as f32;
+        Ok(average)
+    }
+    fn aabb(&self, vertices: Range) -> Result<Aabb, MeshieError> {
+        attributes::check_range(self, vertices)?;
+        let values = &positions(self)?[vertices.iter()];
+        Aabb::from_points(values.iter().map(|p| Vec3::from(*p))).ok_or(MeshieError::EmptyRange)
+    }
+    fn bounding_sphere(&self, vertices: Range) -> Result<BoundingSphere, MeshieError> {
+        attributes::check_range(self, vertices)?;
+        let values: Vec<Vec3> = positions(self)?[vertices.iter()]
+            .iter()
+            .map(|p| Vec3::from(*p))
+            .collect();
+        BoundingSphere::from_points(&values).ok_or(MeshieError::EmptyRange)
+    }
+    fn extend_mesh(&mut self, vertices: Range, direction: Vec3) -> Result<(), MeshieError> {
+        let center = self.get_center(vertices)?;
+        let values = positions_mut(self)?;
+        for i in vertices.iter() {
+            let vert = Vec3::from_slice_unaligned(&values[i]);
+            if direction.dot(vert - center) > 0.0 {
+                let new_pos = vert + direction;
+                values[i] = [new_pos.x(), new_pos.y(), new_pos.z()];
             }
-            _ => panic!("no uvs on mesh??"),
         }
+        Ok(())
+    }
+    /// moves the triangles in the range onto a copy of their vertices `direction` away and
+    /// joins the two with side walls, returning the range of the new cap
+    fn extrude_mesh(&mut self, vertices: Range, direction: Vec3) -> Result<Range, MeshieError> {
+        extrude::extrude(self, vertices, direction)
+    }
+    fn set_uvs(&mut self, vertices: Range, uv: Vec<[f32; 2]>) -> Result<(), MeshieError> {
+        check_length(&uv, vertices)?;
+        let values = uvs_mut(self)?;
+        check_values(values, vertices)?;
+        for i in vertices.iter() {
+            values[i] = uv[i - vertices.start];
+        }
+        Ok(())
     }
 
-    fn get_positions(&self, vertices: Range) -> Vec<[f32; 3]> {
-        match self.attributes[0].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref values) => {
-                let mut result = Vec::new();
-                for i in vertices.iter() {
-                    result.push(values[i]);
-                }
-                result
-            }
-            _ => panic!("no positions on mesh??"),
+    /// tints the vertices, adding a color attribute to the mesh if it doesn't have one
+    fn set_colors(&mut self, vertices: Range, color: [f32; 4]) -> Result<(), MeshieError> {
+        let values = ensure_colors(self)?;
+        check_values(values, vertices)?;
+        for i in vertices.iter() {
+            values[i] = color;
         }
+        Ok(())
     }
 
-    fn set_positions(&mut self, vertices: Range, positions: Vec<[f32; 3]>) {
-        match self.attributes[0].values {
-            bevy::render::mesh::VertexAttributeValues::Float3(ref mut values) => {
-                for i in vertices.iter() {
-                    values[i] = positions[i - vertices.start];
-                }
-            }
-            _ => panic!("no positions on mesh??"),
+    fn get_positions(&self, vertices: Range) -> Result<Vec<[f32; 3]>, MeshieError> {
+        let values = positions(self)?;
+        check_values(values, vertices)?;
+        Ok(values[vertices.iter()].to_vec())
+    }
+
+    fn set_positions(
+        &mut self,
+        vertices: Range,
+        positions: Vec<[f32; 3]>,
+    ) -> Result<(), MeshieError> {
+        check_length(&positions, vertices)?;
+        let values = positions_mut(self)?;
+        check_values(values, vertices)?;
+        for i in vertices.iter() {
+            values[i] = positions[i - vertices.start];
         }
+        Ok(())
+    }
+
+    /// merges vertices in the range that match within `epsilon`, returning how many were
+    /// removed. the range shrinks by that many and later vertices move down to match
+    fn weld(&mut self, vertices: Range, epsilon: f32) -> Result<usize, MeshieError> {
+        weld::weld(self, vertices, epsilon)
+    }
+
+    /// checks the index buffer and attributes for anything that would render wrong
+    fn validate(&self) -> Result<ValidationReport, MeshieError> {
+        validate::validate(self)
+    }
+    /// drops the triangles `validate` finds broken, returning how many were dropped
+    fn repair(&mut self) -> Result<usize, MeshieError> {
+        validate::repair(self)
     }
 }
 
-fn add_indices(mesh: &mut Vec<u32>, other: &Vec<u32>, count: usize) {
-    mesh.extend(other.iter().map(|o| *o + count as u32))
+fn reverse_winding<T>(indices: &mut [T]) {
+    for triangle in indices.chunks_exact_mut(3) {
+        triangle.swap(0, 2);
+    }
 }
-fn add_positions(mesh: &mut Vec<[f32; 3]>, other: &Vec<[f32; 3]>) {
-    mesh.extend(other)
+fn reverse_winding_in<T: Copy>(indices: &mut [T], inside: impl Fn(T) -> bool) {
+    for triangle in indices.chunks_exact_mut(3) {
+        if triangle.iter().all(|i| inside(*i)) {
+            triangle.swap(0, 2);
+        }
+    }
 }
-fn add_normals(mesh: &mut Vec<[f32; 3]>, other: &Vec<[f32; 3]>) {
-    mesh.extend(other)
+/// appends `other` to `mesh`, offsetting it by `count` vertices. the result stays U16 while
+/// both sides are U16 and the merged mesh fits in `u16::MAX` vertices, otherwise it is U32.
+/// a mesh without indices draws its vertices in order, so those triangles are written out
+/// first, leaving off any vertices that don't make a whole triangle
+fn merge_indices(
+    mesh: Option<Indices>,
+    other: &Indices,
+    count: usize,
+    vertex_count: usize,
+) -> Indices {
+    match (mesh, other) {
+        (None, _) => {
+            let drawn = count - count % 3;
+            let implicit = if count <= u16::MAX as usize {
+                Indices::U16((0..drawn as u16).collect())
+            } else {
+                Indices::U32((0..drawn as u32).collect())
+            };
+            merge_indices(Some(implicit), other, count, vertex_count)
+        }
+        (Some(Indices::U16(mut values)), Indices::U16(addons))
+            if vertex_count <= u16::MAX as usize =>
+        {
+            add_indices_u16(&mut values, addons, count);
+            Indices::U16(values)
+        }
+        (Some(Indices::U16(values)), addons) => {
+            let mut values = values.iter().map(|i| *i as u32).collect();
+            add_indices(&mut values, &indices_to_u32(addons), count);
+            Indices::U32(values)
+        }
+        (Some(Indices::U32(mut values)), addons) => {
+            add_indices(&mut values, &indices_to_u32(addons), count);
+            Indices::U32(values)
+        }
+    }
 }
-fn add_uvs(mesh: &mut Vec<[f32; 2]>, other: &Vec<[f32; 2]>) {
-    mesh.extend(other)
+/// keeps the triangles whose vertices all survive `remap`, rewritten to their new indices
+fn remove_triangles<T: Copy>(indices: &[T], remap: impl Fn(T) -> Option<T>) -> Vec<T> {
+    let mut result = Vec::with_capacity(indices.len());
+    for triangle in indices.chunks_exact(3) {
+        if let (Some(a), Some(b), Some(c)) =
+            (remap(triangle[0]), remap(triangle[1]), remap(triangle[2]))
+        {
+            result.extend_from_slice(&[a, b, c]);
+        }
+    }
+    result
+}
+/// stores `values` back as U16 when `like` was U16 and every index still fits
+pub(crate) fn indices_like(like: &Indices, values: Vec<u32>, vertex_count: usize) -> Indices {
+    match like {
+        Indices::U16(_) if vertex_count <= u16::MAX as usize => {
+            Indices::U16(values.iter().map(|i| *i as u16).collect())
+        }
+        _ => Indices::U32(values),
+    }
+}
+pub(crate) fn indices_to_u32(indices: &Indices) -> Vec<u32> {
+    match indices {
+        Indices::U16(values) => values.iter().map(|i| *i as u32).collect(),
+        Indices::U32(values) => values.clone(),
+    }
+}
+fn index_count(indices: &Indices) -> usize {
+    match indices {
+        Indices::U16(values) => values.len(),
+        Indices::U32(values) => values.len(),
+    }
+}
+fn add_indices(mesh: &mut Vec<u32>, other: &Vec<u32>, count: usize) {
+    mesh.extend(other.iter().map(|o| *o + count as u32))
+}
+fn add_indices_u16(mesh: &mut Vec<u16>, other: &Vec<u16>, count: usize) {
+    mesh.extend(other.iter().map(|o| *o + count as u16))
 }
-
-// pub fn remove(&mut Mesh, indices)
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use bevy::{
-        math::vec2,
-        prelude::{shape, Mesh},
+        math::{vec2, vec3},
+        prelude::{shape, Mesh, Transform},
+        render::{
+            mesh::{VertexAttribute, VertexAttributeValues},
+            pipeline::PrimitiveTopology,
+        },
     };
 
     #[test]
@@ -226,7 +494,412 @@ mod tests {
             size: vec2(200., 200.),
             flip: false,
         });
-        mesh_one.add_mesh(&mesh_two);
+        mesh_one.add_mesh(&mesh_two).unwrap();
         println!("{:?}", mesh_one);
     }
+
+    fn quad() -> Mesh {
+        Mesh::from(shape::Quad {
+            size: vec2(200., 200.),
+            flip: false,
+        })
+    }
+    fn quad_u16() -> Mesh {
+        let mut mesh = quad();
+        if let Some(Indices::U32(values)) = mesh.indices.take() {
+            mesh.indices = Some(Indices::U16(values.iter().map(|i| *i as u16).collect()));
+        }
+        mesh
+    }
+    fn index_values(mesh: &Mesh) -> Vec<u32> {
+        indices_to_u32(mesh.indices.as_ref().expect("mesh indices"))
+    }
+
+    #[test]
+    fn add_mesh_all_index_combinations() {
+        let makers: [fn() -> Mesh; 2] = [quad, quad_u16];
+        for make_one in makers.iter() {
+            for make_two in makers.iter() {
+                let mut mesh_one = make_one();
+                let mesh_two = make_two();
+                let range = mesh_one.add_mesh(&mesh_two).unwrap();
+                assert_eq!(range, Range { start: 4, end: 8 });
+                assert_eq!(attributes::vertex_count(&mesh_one).unwrap(), 8);
+                assert_eq!(attributes::normals(&mesh_one).unwrap().len(), 8);
+                assert_eq!(attributes::uvs(&mesh_one).unwrap().len(), 8);
+
+                let mut expected = index_values(&mesh_two);
+                let offset: Vec<u32> = expected.iter().map(|i| i + 4).collect();
+                expected.extend(offset);
+                assert_eq!(index_values(&mesh_one), expected);
+
+                let expect_u16 = matches!(make_one().indices, Some(Indices::U16(_)))
+                    && matches!(mesh_two.indices, Some(Indices::U16(_)));
+                assert_eq!(
+                    matches!(mesh_one.indices, Some(Indices::U16(_))),
+                    expect_u16
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn add_mesh_without_indices() {
+        // two triangles drawn straight from the vertex order
+        let mut mesh_one = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh_one.attributes.push(VertexAttribute::position(vec![
+            [0., 0., 0.],
+            [1., 0., 0.],
+            [0., 1., 0.],
+            [1., 0., 0.],
+            [1., 1., 0.],
+            [0., 1., 0.],
+        ]));
+        mesh_one
+            .attributes
+            .push(VertexAttribute::normal(vec![[0., 0., 1.]; 6]));
+        mesh_one
+            .attributes
+            .push(VertexAttribute::uv(vec![[0., 0.]; 6]));
+        let range = mesh_one.add_mesh(&quad_u16()).unwrap();
+        assert_eq!(range, Range { start: 6, end: 10 });
+        assert_eq!(
+            index_values(&mesh_one),
+            vec![0, 1, 2, 3, 4, 5, 6, 8, 7, 6, 9, 8]
+        );
+        assert!(matches!(mesh_one.indices, Some(Indices::U16(_))));
+
+        // a vertex left over from the last whole triangle isn't drawn, so it stays that way
+        let mut mesh_one = quad_u16();
+        mesh_one.indices = None;
+        mesh_one.add_mesh(&quad()).unwrap();
+        assert_eq!(index_values(&mesh_one), vec![0, 1, 2, 4, 6, 5, 4, 7, 6]);
+    }
+
+    #[test]
+    fn add_mesh_promotes_u16_past_limit() {
+        let mut mesh_one = quad_u16();
+        while attributes::vertex_count(&mesh_one).unwrap() + 4 <= u16::MAX as usize {
+            mesh_one.add_mesh(&quad_u16()).unwrap();
+        }
+        assert!(matches!(mesh_one.indices, Some(Indices::U16(_))));
+        let count = attributes::vertex_count(&mesh_one).unwrap();
+
+        let range = mesh_one.add_mesh(&quad_u16()).unwrap();
+        assert_eq!(
+            range,
+            Range {
+                start: count,
+                end: count + 4
+            }
+        );
+        assert!(matches!(mesh_one.indices, Some(Indices::U32(_))));
+        let indices = index_values(&mesh_one);
+        assert_eq!(indices.len(), (count + 4) / 4 * 6);
+        assert_eq!(
+            indices[indices.len() - 6..].to_vec(),
+            vec![0, 2, 1, 0, 3, 2]
+                .iter()
+                .map(|i| i + count as u32)
+                .collect::<Vec<u32>>()
+        );
+    }
+
+    #[test]
+    fn reverse_triangles_u16_and_u32() {
+        for mesh in &mut [quad(), quad_u16()] {
+            mesh.reverse_triangles();
+            assert_eq!(index_values(mesh), vec![1, 2, 0, 2, 3, 0]);
+        }
+    }
+
+    #[test]
+    fn add_mesh_by_attribute_name() {
+        let mut mesh_one = quad();
+        mesh_one.attributes.reverse();
+        mesh_one.attributes.push(VertexAttribute {
+            name: "Vertex_Texture".into(),
+            values: VertexAttributeValues::Float(vec![1.0; 4]),
+        });
+        let mut mesh_two = quad();
+        mesh_two.attributes.push(VertexAttribute {
+            name: "Vertex_Texture".into(),
+            values: VertexAttributeValues::Float(vec![2.0; 4]),
+        });
+        mesh_two
+            .translate_mesh(Range { start: 0, end: 4 }, vec3(0.0, 0.0, 5.0))
+            .unwrap();
+
+        let range = mesh_one.add_mesh(&mesh_two).unwrap();
+        assert_eq!(
+            mesh_one.get_positions(range).unwrap(),
+            mesh_two.get_positions(Range { start: 0, end: 4 }).unwrap()
+        );
+        assert_eq!(attributes::uvs(&mesh_one).unwrap().len(), 8);
+        assert_eq!(attributes::normals(&mesh_one).unwrap().len(), 8);
+        match attributes::attribute(&mesh_one, "Vertex_Texture").unwrap() {
+            VertexAttributeValues::Float(values) => {
+                assert_eq!(values, &vec![1.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0])
+            }
+            _ => panic!("Vertex_Texture changed type"),
+        }
+    }
+
+    #[test]
+    fn missing_attribute_is_an_error() {
+        let mut mesh_one = quad();
+        let mut mesh_two = quad();
+        mesh_two
+            .attributes
+            .retain(|attribute| attribute.name != VertexAttribute::UV);
+        assert_eq!(
+            mesh_one.add_mesh(&mesh_two),
+            Err(MeshieError::MissingAttribute(
+                VertexAttribute::UV.to_string()
+            ))
+        );
+        assert_eq!(attributes::vertex_count(&mesh_one), Ok(4));
+        assert_eq!(
+            mesh_two.set_uvs(Range { start: 0, end: 4 }, vec![[0.0, 0.0]; 4]),
+            Err(MeshieError::MissingAttribute(
+                VertexAttribute::UV.to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn remove_mesh_compacts_indices() {
+        for make in [quad, quad_u16].iter() {
+            let mut mesh = make();
+            let middle = mesh.add_mesh(&make()).unwrap();
+            let last = mesh.add_mesh(&make()).unwrap();
+            mesh.translate_mesh(last, vec3(0.0, 0.0, 7.0)).unwrap();
+            let last_positions = mesh.get_positions(last).unwrap();
+
+            let remap = mesh.remove_mesh(middle).unwrap();
+            assert_eq!(attributes::vertex_count(&mesh), Ok(8));
+            assert_eq!(attributes::normals(&mesh).unwrap().len(), 8);
+            assert_eq!(attributes::uvs(&mesh).unwrap().len(), 8);
+            assert_eq!(
+                index_values(&mesh),
+                vec![0, 2, 1, 0, 3, 2, 4, 6, 5, 4, 7, 6]
+            );
+            assert_eq!(
+                matches!(mesh.indices, Some(Indices::U16(_))),
+                matches!(make().indices, Some(Indices::U16(_)))
+            );
+
+            let last = remap.range(last).unwrap();
+            assert_eq!(last, Range { start: 4, end: 8 });
+            assert_eq!(mesh.get_positions(last).unwrap(), last_positions);
+            assert_eq!(remap.range(middle), None);
+            assert_eq!(
+                remap.range(Range { start: 0, end: 4 }),
+                Some(Range { start: 0, end: 4 })
+            );
+            assert_eq!(
+                remap.table(12),
+                vec![
+                    Some(0),
+                    Some(1),
+                    Some(2),
+                    Some(3),
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(4),
+                    Some(5),
+                    Some(6),
+                    Some(7)
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn remove_mesh_drops_triangles_touching_range() {
+        let mut mesh = quad();
+        mesh.remove_mesh(Range { start: 3, end: 4 }).unwrap();
+        assert_eq!(attributes::vertex_count(&mesh), Ok(3));
+        assert_eq!(index_values(&mesh), vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn remove_mesh_out_of_bounds() {
+        let mut mesh = quad();
+        let vertices = Range { start: 2, end: 6 };
+        assert_eq!(
+            mesh.remove_mesh(vertices),
+            Err(MeshieError::OutOfBounds {
+                vertices,
+                vertex_count: 4
+            })
+        );
+        assert_eq!(attributes::vertex_count(&mesh), Ok(4));
+    }
+
+    #[test]
+    fn bad_ranges_are_errors() {
+        let mut mesh = quad();
+        let vertices = Range { start: 2, end: 6 };
+        let out_of_bounds = Err(MeshieError::OutOfBounds {
+            vertices,
+            vertex_count: 4,
+        });
+        assert_eq!(mesh.translate_mesh(vertices, Vec3::one()), out_of_bounds);
+        assert_eq!(mesh.rotate_mesh(vertices, Quat::identity()), out_of_bounds);
+        assert_eq!(
+            mesh.transform_mesh(vertices, Mat4::identity()),
+            out_of_bounds
+        );
+        assert_eq!(mesh.extend_mesh(vertices, Vec3::one()), out_of_bounds);
+        assert_eq!(mesh.set_colors(vertices, attributes::WHITE), out_of_bounds);
+        assert_eq!(
+            mesh.get_positions(vertices),
+            Err(out_of_bounds.unwrap_err())
+        );
+        assert_eq!(
+            mesh.set_uvs(vertices, vec![[0.0, 0.0]; 4]),
+            Err(MeshieError::OutOfBounds {
+                vertices,
+                vertex_count: 4
+            })
+        );
+        assert_eq!(
+            mesh.get_center(Range { start: 2, end: 2 }),
+            Err(MeshieError::EmptyRange)
+        );
+
+        let vertices = Range { start: 0, end: 4 };
+        let short = Err(MeshieError::WrongLength {
+            expected: 4,
+            found: 3,
+        });
+        assert_eq!(mesh.set_uvs(vertices, vec![[0.0, 0.0]; 3]), short);
+        assert_eq!(mesh.set_positions(vertices, vec![[0.0; 3]; 3]), short);
+        assert_eq!(
+            mesh.get_positions(vertices),
+            Ok(quad().get_positions(vertices).unwrap())
+        );
+    }
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 0.0001, "{:?} != {:?}", a, b);
+    }
+    /// checks that every triangle's stored normals agree with the normal of its positions
+    fn assert_normals_match_faces(mesh: &Mesh) {
+        let positions = attributes::positions(mesh).unwrap();
+        let normals = attributes::normals(mesh).unwrap();
+        for triangle in index_values(mesh).chunks_exact(3) {
+            let a = Vec3::from(positions[triangle[0] as usize]);
+            let b = Vec3::from(positions[triangle[1] as usize]);
+            let c = Vec3::from(positions[triangle[2] as usize]);
+            let face = (b - a).cross(c - a).normalize();
+            for i in triangle {
+                assert_close(Vec3::from(normals[*i as usize]), face);
+            }
+        }
+    }
+
+    #[test]
+    fn transform_mesh_matches_transform() {
+        let transforms = [
+            Transform::from_translation_rotation_scale(
+                vec3(1.0, -2.0, 3.0),
+                Quat::from_rotation_y(0.7),
+                2.5,
+            ),
+            Transform::new(Mat4::from_scale_rotation_translation(
+                vec3(3.0, 0.5, 1.5),
+                Quat::from_rotation_x(1.2),
+                vec3(0.0, 4.0, 0.0),
+            )),
+            Transform::new(Mat4::from_scale(vec3(-1.0, 1.0, 1.0))),
+        ];
+        for transform in transforms.iter() {
+            let original = Mesh::from(shape::Cube { size: 1.0 });
+            let mut mesh = Mesh::from(shape::Quad {
+                size: vec2(1.0, 1.0),
+                flip: false,
+            });
+            let range = mesh.add_mesh(&original).unwrap();
+            mesh.transform_mesh(range, *transform.value()).unwrap();
+
+            let moved = mesh.get_positions(range).unwrap();
+            let expected = original.get_positions(Range { start: 0, end: 24 }).unwrap();
+            for (moved, expected) in moved.iter().zip(expected.iter()) {
+                assert_close(
+                    Vec3::from(*moved),
+                    transform.value().transform_point3(Vec3::from(*expected)),
+                );
+            }
+            mesh.remove_mesh(Range { start: 0, end: 4 }).unwrap();
+            assert_normals_match_faces(&mesh);
+        }
+    }
+
+    #[test]
+    fn scale_mesh_around_center_and_pivot() {
+        let mut mesh = quad();
+        let range = mesh.add_mesh(&quad()).unwrap();
+        mesh.translate_mesh(range, vec3(300.0, 0.0, 0.0)).unwrap();
+
+        mesh.scale_mesh(range, vec3(2.0, 0.5, 1.0)).unwrap();
+        assert_close(mesh.get_center(range).unwrap(), vec3(300.0, 0.0, 0.0));
+        assert_eq!(
+            mesh.get_positions(range).unwrap()[0],
+            [300.0 - 200.0, -50.0, 0.0]
+        );
+        assert_eq!(
+            mesh.get_positions(Range { start: 0, end: 4 }).unwrap(),
+            quad().get_positions(Range { start: 0, end: 4 }).unwrap()
+        );
+
+        mesh.scale_mesh_around(range, vec3(0.5, 1.0, 1.0), Vec3::zero())
+            .unwrap();
+        assert_close(mesh.get_center(range).unwrap(), vec3(150.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn mirroring_keeps_winding_outward() {
+        let mut mesh = quad();
+        let range = mesh.add_mesh(&quad()).unwrap();
+        mesh.scale_mesh(range, vec3(-1.0, 1.0, 1.0)).unwrap();
+        assert_eq!(
+            index_values(&mesh),
+            vec![0, 2, 1, 0, 3, 2, 5, 6, 4, 6, 7, 4]
+        );
+        assert_normals_match_faces(&mesh);
+    }
+
+    #[test]
+    fn colors_are_padded_when_merging() {
+        let red = [1.0, 0.0, 0.0, 1.0];
+        let blue = [0.0, 0.0, 1.0, 1.0];
+        let mut colored = quad();
+        colored.set_colors(Range { start: 0, end: 4 }, red).unwrap();
+
+        let mut mesh = quad();
+        let first = mesh.add_mesh(&colored).unwrap();
+        let second = mesh.add_mesh(&quad()).unwrap();
+        mesh.set_colors(second, blue).unwrap();
+        let colors = attributes::colors(&mesh).unwrap();
+        assert_eq!(colors.len(), 12);
+        assert_eq!(colors[0..4].to_vec(), vec![attributes::WHITE; 4]);
+        assert_eq!(colors[first.iter()].to_vec(), vec![red; 4]);
+        assert_eq!(colors[second.iter()].to_vec(), vec![blue; 4]);
+
+        let mut colored_first = colored;
+        colored_first.add_mesh(&quad()).unwrap();
+        let colors = attributes::colors(&colored_first).unwrap();
+        assert_eq!(colors[0..4].to_vec(), vec![red; 4]);
+        assert_eq!(colors[4..8].to_vec(), vec![attributes::WHITE; 4]);
+
+        mesh.remove_mesh(first).unwrap();
+        assert_eq!(
+            attributes::colors(&mesh).unwrap()[4..8].to_vec(),
+            vec![blue; 4]
+        );
+    }
 }
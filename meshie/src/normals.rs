@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use bevy::{
+    math::{Vec2, Vec3},
+    prelude::Mesh,
+    render::mesh::{VertexAttribute, VertexAttributeValues},
+};
+use ds_range::Range;
+
+use crate::{attributes, indices_to_u32, MeshieError};
+
+/// Triangles whose three vertices all sit inside `vertices`.
+pub(crate) fn triangles_in(mesh: &Mesh, vertices: Range) -> Result<Vec<[usize; 3]>, MeshieError> {
+    let indices = mesh.indices.as_ref().ok_or(MeshieError::MissingIndices)?;
+    let inside = |i: usize| i >= vertices.start && i < vertices.end;
+    Ok(indices_to_u32(indices)
+        .chunks_exact(3)
+        .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+        .filter(|t| t.iter().all(|i| inside(*i)))
+        .collect())
+}
+
+/// Sums the angle weighted normal of every triangle in the range onto its vertices. With
+/// `smooth` set, vertices that sit at the same position share their sums.
+pub(crate) fn compute_normals(
+    mesh: &mut Mesh,
+    vertices: Range,
+    smooth: bool,
+) -> Result<(), MeshieError> {
+    attributes::check_range(mesh, vertices)?;
+    let triangles = triangles_in(mesh, vertices)?;
+    let positions = attributes::positions(mesh)?;
+
+    let mut sums = vec![Vec3::zero(); vertices.len()];
+    for triangle in triangles.iter() {
+        let a = Vec3::from(positions[triangle[0]]);
+        let b = Vec3::from(positions[triangle[1]]);
+        let c = Vec3::from(positions[triangle[2]]);
+        let face = (b - a).cross(c - a);
+        if face.length_squared() == 0.0 {
+            continue;
+        }
+        let face = face.normalize();
+        // weighting by the corner angle keeps the result independent of how faces are split
+        let corners = [(a, b, c), (b, c, a), (c, a, b)];
+        for (i, (corner, next, prev)) in triangle.iter().zip(corners.iter()) {
+            let angle = angle_between(*next - *corner, *prev - *corner);
+            sums[i - vertices.start] += face * angle;
+        }
+    }
+    if smooth {
+        let mut shared: HashMap<[u32; 3], Vec3> = HashMap::new();
+        for i in vertices.iter() {
+            *shared.entry(position_key(positions[i])).or_default() += sums[i - vertices.start];
+        }
+        for i in vertices.iter() {
+            sums[i - vertices.start] = shared[&position_key(positions[i])];
+        }
+    }
+
+    if let Err(MeshieError::MissingAttribute(_)) = attributes::normals(mesh) {
+        let count = attributes::vertex_count(mesh)?;
+        mesh.attributes
+            .push(VertexAttribute::normal(vec![[0.0, 0.0, 0.0]; count]));
+    }
+    let normals = attributes::normals_mut(mesh)?;
+    for i in vertices.iter() {
+        let sum = sums[i - vertices.start];
+        // vertices without a triangle in the range keep the normal they had
+        if sum.length_squared() > 0.0 {
+            let normal = sum.normalize();
+            normals[i] = [normal.x(), normal.y(), normal.z()];
+        }
+    }
+    Ok(())
+}
+
+/// Builds `Vertex_Tangent` from the positions, normals and uvs of the triangles in the range.
+/// The w component holds the handedness of the bitangent.
+pub(crate) fn compute_tangents(mesh: &mut Mesh, vertices: Range) -> Result<(), MeshieError> {
+    attributes::check_range(mesh, vertices)?;
+    let triangles = triangles_in(mesh, vertices)?;
+    let positions = attributes::positions(mesh)?;
+    let uvs = attributes::uvs(mesh)?;
+    let normals = attributes::normals(mesh)?;
+
+    let mut tangents = vec![Vec3::zero(); vertices.len()];
+    let mut bitangents = vec![Vec3::zero(); vertices.len()];
+    for triangle in triangles.iter() {
+        let p0 = Vec3::from(positions[triangle[0]]);
+        let edge1 = Vec3::from(positions[triangle[1]]) - p0;
+        let edge2 = Vec3::from(positions[triangle[2]]) - p0;
+        let uv0 = Vec2::from(uvs[triangle[0]]);
+        let duv1 = Vec2::from(uvs[triangle[1]]) - uv0;
+        let duv2 = Vec2::from(uvs[triangle[2]]) - uv0;
+        let det = duv1.x() * duv2.y() - duv2.x() * duv1.y();
+        if det.abs() < std::f32::EPSILON {
+            continue;
+        }
+        let r = 1.0 / det;
+        let tangent = (edge1 * duv2.y() - edge2 * duv1.y()) * r;
+        let bitangent = (edge2 * duv1.x() - edge1 * duv2.x()) * r;
+        for i in triangle {
+            tangents[i - vertices.start] += tangent;
+            bitangents[i - vertices.start] += bitangent;
+        }
+    }
+
+    let mut result = Vec::with_capacity(vertices.len());
+    for i in vertices.iter() {
+        let normal = Vec3::from(normals[i]);
+        let tangent = tangents[i - vertices.start];
+        // Gram-Schmidt against the normal so the tangent lies in the surface
+        let tangent = tangent - normal * normal.dot(tangent);
+        if tangent.length_squared() > 0.0 {
+            let tangent = tangent.normalize();
+            let w = if normal.cross(tangent).dot(bitangents[i - vertices.start]) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+            result.push(Some([tangent.x(), tangent.y(), tangent.z(), w]));
+        } else {
+            result.push(None);
+        }
+    }
+
+    if let Err(MeshieError::MissingAttribute(_)) = attributes::tangents(mesh) {
+        let count = attributes::vertex_count(mesh)?;
+        mesh.attributes.push(VertexAttribute {
+            name: attributes::TANGENT.into(),
+            values: VertexAttributeValues::Float4(vec![[1.0, 0.0, 0.0, 1.0]; count]),
+        });
+    }
+    let values = attributes::tangents_mut(mesh)?;
+    for (i, tangent) in vertices.iter().zip(result) {
+        if let Some(tangent) = tangent {
+            values[i] = tangent;
+        }
+    }
+    Ok(())
+}
+
+fn angle_between(a: Vec3, b: Vec3) -> f32 {
+    (a.dot(b) / (a.length() * b.length()))
+        .max(-1.0)
+        .min(1.0)
+        .acos()
+}
+
+fn position_key(position: [f32; 3]) -> [u32; 3] {
+    // +0.0 and -0.0 should land on the same key
+    let bits = |v: f32| if v == 0.0 { 0 } else { v.to_bits() };
+    [bits(position[0]), bits(position[1]), bits(position[2])]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Meshie;
+    use bevy::{
+        math::{vec2, vec3},
+        prelude::shape,
+    };
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!((a - b).length() < 0.0001, "{:?} != {:?}", a, b);
+    }
+    fn scrambled_normals(mesh: &mut Mesh) {
+        for normal in attributes::normals_mut(mesh).unwrap() {
+            *normal = [0.0, 1.0, 0.0];
+        }
+    }
+
+    #[test]
+    fn cube_flat_normals() {
+        let original = Mesh::from(shape::Cube { size: 1.0 });
+        let mut mesh = Mesh::from(shape::Cube { size: 1.0 });
+        scrambled_normals(&mut mesh);
+        let range = attributes::vertex_range(&mesh).unwrap();
+        mesh.compute_flat_normals(range).unwrap();
+        let expected = attributes::normals(&original).unwrap();
+        for (normal, expected) in attributes::normals(&mesh).unwrap().iter().zip(expected) {
+            assert_close(Vec3::from(*normal), Vec3::from(*expected));
+        }
+    }
+
+    #[test]
+    fn cube_smooth_normals_point_out_of_corners() {
+        let mut mesh = Mesh::from(shape::Cube { size: 1.0 });
+        let range = attributes::vertex_range(&mesh).unwrap();
+        mesh.compute_smooth_normals(range).unwrap();
+        let positions = attributes::positions(&mesh).unwrap();
+        for (normal, position) in attributes::normals(&mesh).unwrap().iter().zip(positions) {
+            assert_close(Vec3::from(*normal), Vec3::from(*position).normalize());
+        }
+    }
+
+    #[test]
+    fn extended_quad_normals() {
+        let mut mesh = Mesh::from(shape::Quad {
+            size: vec2(200., 200.),
+            flip: false,
+        });
+        let other = mesh.add_mesh(&mesh_quad()).unwrap();
+        mesh.extend_mesh(other, vec3(100.0, 0.0, 100.0)).unwrap();
+        mesh.compute_flat_normals(other).unwrap();
+        // the right edge moved up by 100 over a width of 300
+        let tilted = vec3(-1.0, 0.0, 3.0).normalize();
+        for normal in attributes::normals(&mesh).unwrap()[other.iter()].iter() {
+            assert_close(Vec3::from(*normal), tilted);
+        }
+        // vertices outside the range are left alone
+        for normal in attributes::normals(&mesh).unwrap()[0..4].iter() {
+            assert_eq!(*normal, [0.0, 0.0, 1.0]);
+        }
+
+        mesh.compute_smooth_normals(other).unwrap();
+        for normal in attributes::normals(&mesh).unwrap()[other.iter()].iter() {
+            assert_close(Vec3::from(*normal), tilted);
+        }
+    }
+
+    #[test]
+    fn quad_tangents() {
+        let mut mesh = mesh_quad();
+        assert!(attributes::tangents(&mesh).is_err());
+        let range = attributes::vertex_range(&mesh).unwrap();
+        mesh.compute_tangents(range).unwrap();
+        for tangent in attributes::tangents(&mesh).unwrap() {
+            // u runs along +x, v runs down the quad so the bitangent is flipped
+            assert_eq!(*tangent, [1.0, 0.0, 0.0, -1.0]);
+        }
+    }
+
+    fn mesh_quad() -> Mesh {
+        Mesh::from(shape::Quad {
+            size: vec2(200., 200.),
+            flip: false,
+        })
+    }
+}
@@ -0,0 +1,574 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use bevy::{
+    math::Vec3,
+    prelude::Mesh,
+    render::{
+        mesh::{Indices, VertexAttribute},
+        pipeline::PrimitiveTopology,
+    },
+};
+use ds_range::Range;
+
+use crate::{attributes, indices_to_u32, MeshIssue, Meshie, MeshieError};
+
+/// A named run of vertices, written as an OBJ `g` group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjGroup {
+    pub name: String,
+    pub vertices: Range,
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(io::Error),
+    /// the line (counting from 1) couldn't be read
+    Parse {
+        line: usize,
+        message: String,
+    },
+    Mesh(MeshieError),
+    /// the triangle (counting from 0) has vertices in a group but no group holds all of them
+    SplitTriangle(usize),
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io(error) => write!(f, "obj io error: {}", error),
+            ObjError::Parse { line, message } => write!(f, "obj line {}: {}", line, message),
+            ObjError::Mesh(error) => write!(f, "{}", error),
+            ObjError::SplitTriangle(triangle) => {
+                write!(f, "obj triangle {} is split across groups", triangle)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+impl From<io::Error> for ObjError {
+    fn from(error: io::Error) -> Self {
+        ObjError::Io(error)
+    }
+}
+impl From<MeshieError> for ObjError {
+    fn from(error: MeshieError) -> Self {
+        ObjError::Mesh(error)
+    }
+}
+
+pub fn save_obj(path: impl AsRef<Path>, mesh: &Mesh, groups: &[ObjGroup]) -> Result<(), ObjError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_obj(&mut writer, mesh, groups)?;
+    writer.flush()?;
+    Ok(())
+}
+pub fn load_obj(path: impl AsRef<Path>) -> Result<(Mesh, Vec<ObjGroup>), ObjError> {
+    read_obj(BufReader::new(File::open(path)?))
+}
+
+/// Writes the mesh with its normals and uvs when it has them. Each triangle goes in the first
+/// group holding all three of its vertices, triangles outside every group are written first,
+/// before any `g` line. A triangle with only some of its vertices in groups can't be written
+/// under one `g` line, so it's an error, as is a mesh whose attributes or indices don't line
+/// up with its positions. Uvs are flipped to OBJ's bottom up v.
+pub fn write_obj(
+    writer: &mut impl Write,
+    mesh: &Mesh,
+    groups: &[ObjGroup],
+) -> Result<(), ObjError> {
+    let vertex_count = attributes::check_buffers(mesh)?;
+    let positions = attributes::positions(mesh)?;
+    let normals = attributes::normals(mesh).ok();
+    let uvs = attributes::uvs(mesh).ok();
+    let indices = mesh.indices.as_ref().ok_or(MeshieError::MissingIndices)?;
+    if let Some(group) = groups
+        .iter()
+        .find(|g| g.vertices.start > g.vertices.end || g.vertices.end > vertex_count)
+    {
+        return Err(MeshieError::OutOfBounds {
+            vertices: group.vertices,
+            vertex_count,
+        }
+        .into());
+    }
+    let values = indices_to_u32(indices);
+    if values.len() % 3 != 0 {
+        return Err(MeshieError::Invalid(MeshIssue::TrailingIndices(values.len() % 3)).into());
+    }
+
+    for p in positions {
+        writeln!(writer, "v {} {} {}", p[0], p[1], p[2])?;
+    }
+    if let Some(uvs) = uvs {
+        for uv in uvs {
+            writeln!(writer, "vt {} {}", uv[0], 1.0 - uv[1])?;
+        }
+    }
+    if let Some(normals) = normals {
+        for n in normals {
+            writeln!(writer, "vn {} {} {}", n[0], n[1], n[2])?;
+        }
+    }
+
+    let in_group = |g: &ObjGroup, i: &u32| g.vertices.contains(*i as usize);
+    let mut grouped = vec![Vec::new(); groups.len() + 1];
+    for (t, triangle) in values.chunks_exact(3).enumerate() {
+        let group = match groups
+            .iter()
+            .position(|g| triangle.iter().all(|i| in_group(g, i)))
+        {
+            Some(g) => g + 1,
+            None if triangle
+                .iter()
+                .any(|i| groups.iter().any(|g| in_group(g, i))) =>
+            {
+                return Err(ObjError::SplitTriangle(t));
+            }
+            None => 0,
+        };
+        grouped[group].push(triangle);
+    }
+    for (g, triangles) in grouped.iter().enumerate() {
+        if g > 0 {
+            writeln!(writer, "g {}", groups[g - 1].name)?;
+        }
+        for triangle in triangles {
+            write!(writer, "f")?;
+            for i in triangle.iter() {
+                let i = i + 1;
+                match (uvs.is_some(), normals.is_some()) {
+                    (true, true) => write!(writer, " {}/{}/{}", i, i, i)?,
+                    (true, false) => write!(writer, " {}/{}", i, i)?,
+                    (false, true) => write!(writer, " {}//{}", i, i)?,
+                    (false, false) => write!(writer, " {}", i)?,
+                }
+            }
+            writeln!(writer)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads the positions, uvs, normals, faces and groups of an OBJ file. Faces with more than
+/// three corners are triangulated by ear clipping. Every group gets its own run of vertices,
+/// in the order the file lists them, so it can be handed straight to the `Meshie` range
+/// methods, and no triangle can reach into another group's vertices. Faces before the first
+/// `g` or `o` line end up in a group named `default`. Vertices whose face corner has no normal
+/// get a flat one from their triangles, the rest keep the file's, and vertices without a uv
+/// get `[0, 0]`.
+pub fn read_obj(reader: impl BufRead) -> Result<(Mesh, Vec<ObjGroup>), ObjError> {
+    let mut file = ObjData::default();
+    let mut built = Built::default();
+    let mut groups = Vec::new();
+    let mut name = "default".to_string();
+    let mut faces = Vec::new();
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let parse_error = |message: &str| ObjError::Parse {
+            line: number + 1,
+            message: message.to_string(),
+        };
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("v") => {
+                let v = floats(&mut words, 3).ok_or_else(|| parse_error("bad vertex"))?;
+                file.positions.push([v[0], v[1], v[2]]);
+            }
+            Some("vt") => {
+                let uv = floats(&mut words, 2).ok_or_else(|| parse_error("bad uv"))?;
+                file.uvs.push([uv[0], 1.0 - uv[1]]);
+            }
+            Some("vn") => {
+                let n = floats(&mut words, 3).ok_or_else(|| parse_error("bad normal"))?;
+                file.normals.push([n[0], n[1], n[2]]);
+            }
+            Some("g") | Some("o") => {
+                built.add_group(&mut groups, name, &mut faces, &file);
+                name = words.collect::<Vec<_>>().join(" ");
+            }
+            Some("f") => {
+                let mut face = Vec::new();
+                for word in words {
+                    let mut parts = word.split('/');
+                    let resolve = |part: Option<&str>, len: usize| match part {
+                        None | Some("") => Ok(None),
+                        Some(part) => {
+                            let i: i64 = part.parse().map_err(|_| parse_error("bad face index"))?;
+                            let i = if i < 0 { len as i64 + i } else { i - 1 };
+                            if i < 0 || i as usize >= len {
+                                return Err(parse_error("face index out of range"));
+                            }
+                            Ok(Some(i as usize))
+                        }
+                    };
+                    let v = resolve(parts.next(), file.positions.len())?
+                        .ok_or_else(|| parse_error("face corner has no vertex"))?;
+                    let vt = resolve(parts.next(), file.uvs.len())?;
+                    let vn = resolve(parts.next(), file.normals.len())?;
+                    face.push((v, vt, vn));
+                }
+                if face.len() < 3 {
+                    return Err(parse_error("face has fewer than three corners"));
+                }
+                let points: Vec<Vec3> = face
+                    .iter()
+                    .map(|(v, _, _)| Vec3::from(file.positions[*v]))
+                    .collect();
+                for [a, b, c] in triangulate(&points) {
+                    faces.push([face[a], face[b], face[c]]);
+                }
+            }
+            _ => {}
+        }
+    }
+    built.add_group(&mut groups, name, &mut faces, &file);
+
+    let vertex_count = built.positions.len();
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.attributes
+        .push(VertexAttribute::position(built.positions));
+    mesh.attributes
+        .push(VertexAttribute::normal(built.normals.clone()));
+    mesh.attributes.push(VertexAttribute::uv(built.uvs));
+    mesh.indices = Some(Indices::U32(built.indices));
+    if built.has_normal.contains(&false) {
+        mesh.compute_flat_normals(Range {
+            start: 0,
+            end: vertex_count,
+        })?;
+        // put back the normals the file gave
+        let normals = attributes::normals_mut(&mut mesh)?;
+        for (i, has_normal) in built.has_normal.iter().enumerate() {
+            if *has_normal {
+                normals[i] = built.normals[i];
+            }
+        }
+    }
+    Ok((mesh, groups))
+}
+
+/// a face corner as the file indexes it: position, uv and normal
+type Corner = (usize, Option<usize>, Option<usize>);
+
+#[derive(Default)]
+struct ObjData {
+    positions: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    normals: Vec<[f32; 3]>,
+}
+
+#[derive(Default)]
+struct Built {
+    positions: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    normals: Vec<[f32; 3]>,
+    indices: Vec<u32>,
+    has_normal: Vec<bool>,
+}
+
+impl Built {
+    /// gives the group's faces vertices of their own, sorted by where the file lists them
+    fn add_group(
+        &mut self,
+        groups: &mut Vec<ObjGroup>,
+        name: String,
+        faces: &mut Vec<[Corner; 3]>,
+        file: &ObjData,
+    ) {
+        if faces.is_empty() && name == "default" {
+            return;
+        }
+        let mut corners: Vec<Corner> = faces.iter().flat_map(|f| f.iter().cloned()).collect();
+        corners.sort_unstable();
+        corners.dedup();
+
+        let start = self.positions.len();
+        for (v, vt, vn) in corners.iter() {
+            self.positions.push(file.positions[*v]);
+            self.uvs.push(vt.map_or([0.0, 0.0], |vt| file.uvs[vt]));
+            self.normals
+                .push(vn.map_or([0.0, 0.0, 0.0], |vn| file.normals[vn]));
+            self.has_normal.push(vn.is_some());
+        }
+        for face in faces.drain(..) {
+            for corner in face.iter() {
+                let i = corners.binary_search(corner).unwrap();
+                self.indices.push((start + i) as u32);
+            }
+        }
+        groups.push(ObjGroup {
+            name,
+            vertices: Range {
+                start,
+                end: self.positions.len(),
+            },
+        });
+    }
+}
+
+fn floats(words: &mut std::str::SplitWhitespace, count: usize) -> Option<Vec<f32>> {
+    (0..count).map(|_| words.next()?.parse().ok()).collect()
+}
+
+/// Splits a polygon into triangles by clipping ears, working in the plane of the polygon.
+/// Falls back to a fan when no ear can be found, which only happens for self intersecting
+/// polygons.
+fn triangulate(points: &[Vec3]) -> Vec<[usize; 3]> {
+    // Newell's method, robust for polygons that are slightly off plane
+    let mut normal = Vec3::zero();
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        normal += Vec3::new(
+            (a.y() - b.y()) * (a.z() + b.z()),
+            (a.z() - b.z()) * (a.x() + b.x()),
+            (a.x() - b.x()) * (a.y() + b.y()),
+        );
+    }
+    let is_convex = |a: Vec3, b: Vec3, c: Vec3| (b - a).cross(c - b).dot(normal) > 0.0;
+    let inside = |p: Vec3, a: Vec3, b: Vec3, c: Vec3| {
+        (b - a).cross(p - a).dot(normal) >= 0.0
+            && (c - b).cross(p - b).dot(normal) >= 0.0
+            && (a - c).cross(p - c).dot(normal) >= 0.0
+    };
+
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|i| {
+            let (a, b, c) = (
+                remaining[(i + n - 1) % n],
+                remaining[*i],
+                remaining[(i + 1) % n],
+            );
+            is_convex(points[a], points[b], points[c])
+                && remaining
+                    .iter()
+                    .filter(|p| **p != a && **p != b && **p != c)
+                    .all(|p| !inside(points[*p], points[a], points[b], points[c]))
+        });
+        match ear {
+            Some(i) => {
+                triangles.push([
+                    remaining[(i + n - 1) % n],
+                    remaining[i],
+                    remaining[(i + 1) % n],
+                ]);
+                remaining.remove(i);
+            }
+            None => break,
+        }
+    }
+    for i in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+    }
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{
+        math::{vec2, vec3},
+        prelude::shape,
+    };
+
+    fn quad() -> Mesh {
+        Mesh::from(shape::Quad {
+            size: vec2(2., 2.),
+            flip: false,
+        })
+    }
+
+    fn to_string(mesh: &Mesh, groups: &[ObjGroup]) -> String {
+        let mut bytes = Vec::new();
+        write_obj(&mut bytes, mesh, groups).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn writes_groups_normals_and_uvs() {
+        let mut mesh = quad();
+        let second = mesh.add_mesh(&quad()).unwrap();
+        mesh.translate_mesh(second, vec3(3.0, 0.0, 0.0)).unwrap();
+        let text = to_string(
+            &mesh,
+            &[ObjGroup {
+                name: "right".to_string(),
+                vertices: second,
+            }],
+        );
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "v -1 -1 0");
+        assert_eq!(lines[8], "vt 0 0");
+        assert_eq!(lines[16], "vn 0 0 1");
+        assert_eq!(lines[24], "f 1/1/1 3/3/3 2/2/2");
+        assert_eq!(lines[26], "g right");
+        assert_eq!(lines[27], "f 5/5/5 7/7/7 6/6/6");
+        assert_eq!(lines.len(), 29);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut mesh = quad();
+        let cube = mesh
+            .add_mesh(&Mesh::from(shape::Cube { size: 1.0 }))
+            .unwrap();
+        let groups = vec![
+            ObjGroup {
+                name: "hull".to_string(),
+                vertices: Range { start: 0, end: 4 },
+            },
+            ObjGroup {
+                name: "engine block".to_string(),
+                vertices: cube,
+            },
+        ];
+        let (read, read_groups) = read_obj(to_string(&mesh, &groups).as_bytes()).unwrap();
+        assert_eq!(read_groups, groups);
+        assert_eq!(attributes::positions(&read), attributes::positions(&mesh));
+        assert_eq!(attributes::normals(&read), attributes::normals(&mesh));
+        assert_eq!(attributes::uvs(&read), attributes::uvs(&mesh));
+        assert_eq!(
+            indices_to_u32(read.indices.as_ref().unwrap()),
+            indices_to_u32(mesh.indices.as_ref().unwrap())
+        );
+        // the import works with the rest of meshie
+        let mut merged = quad();
+        merged.add_mesh(&read).unwrap();
+    }
+
+    #[test]
+    fn n_gons_are_triangulated() {
+        // an L shape, concave at vertex 4, with no normals or uvs
+        let text = "
+            v 0 0 0
+            v 2 0 0
+            v 2 1 0
+            v 1 1 0
+            v 1 2 0
+            v 0 2 0
+            f 1 2 3 4 5 6
+            o quad
+            v 5 3 0
+            f 1 2 -1
+        ";
+        let (mesh, groups) = read_obj(text.as_bytes()).unwrap();
+        assert_eq!(groups[0].vertices, Range { start: 0, end: 6 });
+        assert_eq!(groups[1].name, "quad");
+        assert_eq!(groups[1].vertices, Range { start: 6, end: 9 });
+        let report = mesh.validate().unwrap();
+        assert!(report.is_valid(), "{:?}", report.issues);
+        assert_eq!(report.triangle_count, 5);
+
+        // the triangles cover the L exactly, so their areas add up to 3
+        let positions = attributes::positions(&mesh).unwrap();
+        let area: f32 = indices_to_u32(mesh.indices.as_ref().unwrap())[..12]
+            .chunks_exact(3)
+            .map(|t| {
+                let a = Vec3::from(positions[t[0] as usize]);
+                let b = Vec3::from(positions[t[1] as usize]);
+                let c = Vec3::from(positions[t[2] as usize]);
+                let cross = (b - a).cross(c - a);
+                assert!(cross.z() > 0.0);
+                cross.length() / 2.0
+            })
+            .sum();
+        assert!((area - 3.0).abs() < 0.0001);
+        for normal in attributes::normals(&mesh).unwrap() {
+            assert!((Vec3::from(*normal) - Vec3::unit_z()).length() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn only_missing_normals_are_computed() {
+        // the first corner's normal leans over, the file's normal should survive
+        let text = "
+            v 0 0 0
+            v 1 0 0
+            v 0 1 0
+            vn 0.6 0 0.8
+            f 1//1 2 3
+        ";
+        let (mesh, _) = read_obj(text.as_bytes()).unwrap();
+        let normals = attributes::normals(&mesh).unwrap();
+        assert_eq!(normals[0], [0.6, 0.0, 0.8]);
+        assert_eq!(&normals[1..], &[[0.0, 0.0, 1.0], [0.0, 0.0, 1.0]]);
+    }
+
+    #[test]
+    fn triangles_split_across_groups_are_errors() {
+        let mut mesh = quad();
+        let second = mesh.add_mesh(&quad()).unwrap();
+        // a triangle from the first quad's vertices into the second's
+        if let Some(Indices::U32(indices)) = mesh.indices.as_mut() {
+            indices.extend_from_slice(&[0, 1, 4]);
+        }
+        let groups = [ObjGroup {
+            name: "right".to_string(),
+            vertices: second,
+        }];
+        let error = write_obj(&mut Vec::new(), &mesh, &groups).unwrap_err();
+        assert!(matches!(error, ObjError::SplitTriangle(4)));
+        // outside every group it's written before the first `g`
+        assert!(write_obj(&mut Vec::new(), &mesh, &[]).is_ok());
+    }
+
+    #[test]
+    fn broken_meshes_are_refused() {
+        let write = |mesh: &Mesh, groups: &[ObjGroup]| write_obj(&mut Vec::new(), mesh, groups);
+        let is_invalid = |result: Result<(), ObjError>, expected: MeshIssue| match result {
+            Err(ObjError::Mesh(MeshieError::Invalid(issue))) => issue == expected,
+            _ => false,
+        };
+
+        let mut mesh = quad();
+        mesh.indices = Some(Indices::U32(vec![0, 2, 1, 0, 3, 4]));
+        assert!(is_invalid(
+            write(&mesh, &[]),
+            MeshIssue::IndexOutOfRange {
+                triangle: 1,
+                index: 4
+            }
+        ));
+
+        let mut mesh = quad();
+        mesh.indices = Some(Indices::U32(vec![0, 2, 1, 0, 3]));
+        assert!(is_invalid(write(&mesh, &[]), MeshIssue::TrailingIndices(2)));
+
+        let mut mesh = quad();
+        attributes::uvs_mut(&mut mesh).unwrap().pop();
+        assert!(is_invalid(
+            write(&mesh, &[]),
+            MeshIssue::AttributeLength {
+                name: VertexAttribute::UV.to_string(),
+                len: 3
+            }
+        ));
+
+        let backwards = ObjGroup {
+            name: "backwards".to_string(),
+            vertices: Range { start: 3, end: 1 },
+        };
+        assert!(matches!(
+            write(&quad(), &[backwards]),
+            Err(ObjError::Mesh(MeshieError::OutOfBounds { .. }))
+        ));
+    }
+
+    #[test]
+    fn bad_lines_are_reported() {
+        let error = read_obj("v 0 0 0\nv 1 0 0\nf 1 2 3\n".as_bytes()).unwrap_err();
+        assert!(matches!(error, ObjError::Parse { line: 3, .. }));
+        let error = read_obj("v 0 0\n".as_bytes()).unwrap_err();
+        assert!(matches!(error, ObjError::Parse { line: 1, .. }));
+    }
+}
@@ -0,0 +1,71 @@
+use ds_range::Range;
+
+/// Describes how vertex indices moved after `Meshie::remove_mesh` took a range out of a mesh,
+/// so ranges held elsewhere can be updated.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Remap {
+    pub removed: Range,
+}
+
+impl Remap {
+    /// the new index of `vertex`, or `None` if it was removed
+    pub fn vertex(&self, vertex: usize) -> Option<usize> {
+        if vertex < self.removed.start {
+            Some(vertex)
+        } else if vertex < self.removed.end {
+            None
+        } else {
+            Some(vertex - self.removed.len())
+        }
+    }
+    /// the new position of `vertices`, or `None` if any of them were removed. an empty range
+    /// moves with the vertices around it, and is `None` when it sat inside the removed range
+    pub fn range(&self, vertices: Range) -> Option<Range> {
+        if vertices.is_empty() {
+            let at = vertices.start;
+            let at = if at <= self.removed.start {
+                at
+            } else if at < self.removed.end {
+                return None;
+            } else {
+                at - self.removed.len()
+            };
+            return Some(Range { start: at, end: at });
+        }
+        let start = self.vertex(vertices.start)?;
+        let last = self.vertex(vertices.end - 1)?;
+        if last + 1 - start != vertices.len() {
+            return None;
+        }
+        Some(Range {
+            start,
+            end: last + 1,
+        })
+    }
+    /// old index -> new index for the first `vertex_count` vertices of the mesh
+    pub fn table(&self, vertex_count: usize) -> Vec<Option<usize>> {
+        (0..vertex_count).map(|i| self.vertex(i)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_ranges_move_like_the_rest() {
+        let remap = Remap {
+            removed: Range { start: 4, end: 8 },
+        };
+        let at = |i| Range { start: i, end: i };
+        assert_eq!(remap.range(at(2)), Some(at(2)));
+        assert_eq!(remap.range(at(4)), Some(at(4)));
+        assert_eq!(remap.range(at(6)), None);
+        assert_eq!(remap.range(at(8)), Some(at(4)));
+        assert_eq!(remap.range(at(12)), Some(at(8)));
+        assert_eq!(
+            remap.range(Range { start: 12, end: 14 }),
+            Some(Range { start: 8, end: 10 })
+        );
+    }
+}
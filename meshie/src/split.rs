@@ -0,0 +1,199 @@
+use bevy::{prelude::Mesh, render::mesh::VertexAttribute};
+use ds_range::Range;
+
+use crate::{attributes, indices_like, indices_to_u32, Meshie, MeshieError};
+
+/// Where a sub-mesh ended up once it was packed into a chunk.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Placement {
+    pub chunk: usize,
+    pub vertices: Range,
+}
+
+/// Packs sub-meshes into chunk meshes that each stay under a vertex budget.
+///
+/// Sub-meshes are appended to the last chunk until the next one wouldn't fit, at which point a
+/// new chunk is started, so a sub-mesh is never spread across two chunks.
+#[derive(Debug)]
+pub struct Splitter {
+    vertex_budget: usize,
+    chunks: Vec<Mesh>,
+    chunk_vertices: usize,
+    placements: Vec<Placement>,
+}
+
+impl Splitter {
+    pub fn new(vertex_budget: usize) -> Self {
+        Self {
+            vertex_budget,
+            chunks: Vec::new(),
+            chunk_vertices: 0,
+            placements: Vec::new(),
+        }
+    }
+
+    /// packs the mesh into the last chunk, or a new one if it wouldn't fit. errors if the mesh
+    /// alone is over the budget
+    pub fn push(&mut self, mesh: &Mesh) -> Result<Placement, MeshieError> {
+        let vertex_count = attributes::vertex_count(mesh)?;
+        if vertex_count > self.vertex_budget {
+            return Err(MeshieError::OverBudget {
+                vertex_count,
+                budget: self.vertex_budget,
+            });
+        }
+        if self.chunks.is_empty() || self.chunk_vertices + vertex_count > self.vertex_budget {
+            self.chunks.push(Mesh::new(mesh.primitive_topology));
+            self.chunk_vertices = 0;
+        }
+        let chunk = self.chunks.len() - 1;
+        let vertices = self.chunks[chunk].add_mesh(mesh)?;
+        self.chunk_vertices = vertices.end;
+        let placement = Placement { chunk, vertices };
+        self.placements.push(placement);
+        Ok(placement)
+    }
+
+    pub fn chunks(&self) -> &[Mesh] {
+        &self.chunks
+    }
+    /// where each pushed mesh went, in the order they were pushed
+    pub fn placements(&self) -> &[Placement] {
+        &self.placements
+    }
+    pub fn finish(self) -> (Vec<Mesh>, Vec<Placement>) {
+        (self.chunks, self.placements)
+    }
+}
+
+/// Cuts `mesh` into chunks under `vertex_budget`, keeping each of `sub_meshes` whole. Returns
+/// the chunks and where each sub-mesh went.
+pub fn split_mesh(
+    mesh: &Mesh,
+    sub_meshes: &[Range],
+    vertex_budget: usize,
+) -> Result<(Vec<Mesh>, Vec<Placement>), MeshieError> {
+    let mut splitter = Splitter::new(vertex_budget);
+    for vertices in sub_meshes.iter() {
+        splitter.push(&extract(mesh, *vertices)?)?;
+    }
+    Ok(splitter.finish())
+}
+
+/// copies the vertices in the range and the triangles entirely inside it out into a mesh of
+/// their own
+pub(crate) fn extract(mesh: &Mesh, vertices: Range) -> Result<Mesh, MeshieError> {
+    attributes::check_range(mesh, vertices)?;
+    let indices = mesh.indices.as_ref().ok_or(MeshieError::MissingIndices)?;
+    let sources: Vec<usize> = vertices.iter().collect();
+    let inside = |i: u32| i as usize >= vertices.start && (i as usize) < vertices.end;
+    let values: Vec<u32> = indices_to_u32(indices)
+        .chunks_exact(3)
+        .filter(|t| t.iter().all(|i| inside(*i)))
+        .flat_map(|t| {
+            t.iter()
+                .map(|i| *i - vertices.start as u32)
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    let mut sub_mesh = Mesh::new(mesh.primitive_topology);
+    for attribute in mesh.attributes.iter() {
+        sub_mesh.attributes.push(VertexAttribute {
+            name: attribute.name.clone(),
+            values: attributes::gather_values(&attribute.values, &sources),
+        });
+    }
+    sub_mesh.indices = Some(indices_like(indices, values, vertices.len()));
+    Ok(sub_mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{
+        math::{vec2, vec3},
+        prelude::shape,
+    };
+
+    fn quad() -> Mesh {
+        Mesh::from(shape::Quad {
+            size: vec2(2., 2.),
+            flip: false,
+        })
+    }
+
+    #[test]
+    fn sub_meshes_are_never_split() {
+        let mut splitter = Splitter::new(10);
+        let cube = Mesh::from(shape::Cube { size: 1.0 });
+        assert_eq!(
+            splitter.push(&cube).unwrap_err(),
+            MeshieError::OverBudget {
+                vertex_count: 24,
+                budget: 10
+            }
+        );
+        for _ in 0..5 {
+            splitter.push(&quad()).unwrap();
+        }
+        let (chunks, placements) = splitter.finish();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|c| attributes::vertex_count(c).unwrap())
+                .collect::<Vec<_>>(),
+            vec![8, 8, 4]
+        );
+        assert_eq!(
+            placements[3],
+            Placement {
+                chunk: 1,
+                vertices: Range { start: 4, end: 8 }
+            }
+        );
+        assert_eq!(placements[4].chunk, 2);
+        for chunk in chunks.iter() {
+            assert!(chunk.validate().unwrap().is_valid());
+        }
+    }
+
+    #[test]
+    fn split_mesh_keeps_every_sub_mesh() {
+        let mut mesh = quad();
+        let mut sub_meshes = vec![Range { start: 0, end: 4 }];
+        for i in 1..6 {
+            let vertices = mesh.add_mesh(&quad()).unwrap();
+            mesh.translate_mesh(vertices, vec3(i as f32 * 2.0, 0.0, 0.0))
+                .unwrap();
+            sub_meshes.push(vertices);
+        }
+        let cube = mesh
+            .add_mesh(&Mesh::from(shape::Cube { size: 1.0 }))
+            .unwrap();
+        sub_meshes.push(cube);
+
+        let (chunks, placements) = split_mesh(&mesh, &sub_meshes, 30).unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(placements.len(), sub_meshes.len());
+        for (vertices, placement) in sub_meshes.iter().zip(placements.iter()) {
+            assert_eq!(placement.vertices.len(), vertices.len());
+            assert_eq!(
+                chunks[placement.chunk].get_positions(placement.vertices),
+                mesh.get_positions(*vertices)
+            );
+        }
+        assert_eq!(
+            placements[6],
+            Placement {
+                chunk: 1,
+                vertices: Range { start: 0, end: 24 }
+            }
+        );
+        assert_eq!(
+            indices_to_u32(chunks[0].indices.as_ref().unwrap())[6..12].to_vec(),
+            vec![4, 6, 5, 4, 7, 6]
+        );
+    }
+}
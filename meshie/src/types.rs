@@ -1,15 +1,469 @@
-#[derive(Clone)]
-pub(crate) struct Meshie {
-    pub(crate) vertices: Vec<Vertex>,
-    pub(crate) indices: Vec<Triangle>,
+use std::collections::HashMap;
+
+use bevy::{
+    math::{Vec2, Vec3},
+    prelude::Mesh,
+    render::{
+        mesh::{Indices, VertexAttribute},
+        pipeline::PrimitiveTopology,
+    },
+};
+use ds_range::Range;
+
+use crate::{
+    attributes::{self, COLOR, WHITE},
+    indices_like, indices_to_u32, validate, MeshIssue, MeshieError,
+};
+
+/// A mesh held as typed vertices and triangles for edits that are awkward to do on raw
+/// attribute vectors. Converting a bevy `Mesh` in and back out gives the same mesh: attributes
+/// keep their order, ones without a `Vertex` field are carried along untouched and U16
+/// indices stay U16 while they fit.
+#[derive(Debug, Clone)]
+pub struct EditMesh {
+    vertices: Vec<Vertex>,
+    triangles: Vec<Triangle>,
+    topology: PrimitiveTopology,
+    columns: Vec<Column>,
+    wide_indices: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vertex {
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub uv: Vec2,
+    pub color: [f32; 4],
+}
+
+impl Default for Vertex {
+    fn default() -> Self {
+        Self {
+            position: Vec3::zero(),
+            normal: Vec3::zero(),
+            uv: Vec2::zero(),
+            color: WHITE,
+        }
+    }
+}
+
+/// Three vertex indices, wound counter clockwise when looking at the front of the face.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Triangle(pub [u32; 3]);
+
+impl Triangle {
+    /// the three edges going around the face in winding order
+    pub fn edges(&self) -> [(u32, u32); 3] {
+        let [a, b, c] = self.0;
+        [(a, b), (b, c), (c, a)]
+    }
+    pub fn contains(&self, vertex: u32) -> bool {
+        self.0.contains(&vertex)
+    }
+}
+
+/// where each attribute of the bevy mesh lives while it's being edited
+#[derive(Debug, Clone)]
+enum Column {
+    Position,
+    Normal,
+    Uv,
+    Color,
+    Other(VertexAttribute),
+}
+
+impl Default for EditMesh {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EditMesh {
+    /// an empty triangle list with positions, normals and uvs, like the bevy shapes
+    pub fn new() -> Self {
+        Self {
+            vertices: Vec::new(),
+            triangles: Vec::new(),
+            topology: PrimitiveTopology::TriangleList,
+            columns: vec![Column::Position, Column::Normal, Column::Uv],
+            wide_indices: true,
+        }
+    }
+
+    pub fn from_mesh(mesh: &Mesh) -> Result<Self, MeshieError> {
+        let indices = mesh.indices.as_ref().ok_or(MeshieError::MissingIndices)?;
+        let report = validate::validate(mesh)?;
+        if let Some(issue) = report.issues.into_iter().find(|issue| {
+            matches!(
+                issue,
+                MeshIssue::AttributeLength { .. }
+                    | MeshIssue::IndexOutOfRange { .. }
+                    | MeshIssue::TrailingIndices(_)
+            )
+        }) {
+            return Err(MeshieError::Invalid(issue));
+        }
+
+        let mut vertices = vec![Vertex::default(); report.vertex_count];
+        let mut columns = Vec::with_capacity(mesh.attributes.len());
+        for attribute in mesh.attributes.iter() {
+            let column = match &*attribute.name {
+                VertexAttribute::POSITION => {
+                    for (vertex, p) in vertices.iter_mut().zip(attributes::positions(mesh)?) {
+                        vertex.position = Vec3::from(*p);
+                    }
+                    Column::Position
+                }
+                VertexAttribute::NORMAL => {
+                    for (vertex, n) in vertices.iter_mut().zip(attributes::normals(mesh)?) {
+                        vertex.normal = Vec3::from(*n);
+                    }
+                    Column::Normal
+                }
+                VertexAttribute::UV => {
+                    for (vertex, uv) in vertices.iter_mut().zip(attributes::uvs(mesh)?) {
+                        vertex.uv = Vec2::from(*uv);
+                    }
+                    Column::Uv
+                }
+                COLOR => {
+                    for (vertex, color) in vertices.iter_mut().zip(attributes::colors(mesh)?) {
+                        vertex.color = *color;
+                    }
+                    Column::Color
+                }
+                _ => Column::Other(attribute.clone()),
+            };
+            columns.push(column);
+        }
+
+        Ok(Self {
+            vertices,
+            triangles: indices_to_u32(indices)
+                .chunks_exact(3)
+                .map(|t| Triangle([t[0], t[1], t[2]]))
+                .collect(),
+            topology: mesh.primitive_topology,
+            columns,
+            wide_indices: matches!(indices, Indices::U32(_)),
+        })
+    }
+
+    pub fn to_mesh(&self) -> Mesh {
+        let mut mesh = Mesh::new(self.topology);
+        for column in self.columns.iter() {
+            mesh.attributes.push(match column {
+                Column::Position => VertexAttribute::position(
+                    self.vertices
+                        .iter()
+                        .map(|v| [v.position.x(), v.position.y(), v.position.z()])
+                        .collect(),
+                ),
+                Column::Normal => VertexAttribute::normal(
+                    self.vertices
+                        .iter()
+                        .map(|v| [v.normal.x(), v.normal.y(), v.normal.z()])
+                        .collect(),
+                ),
+                Column::Uv => VertexAttribute::uv(
+                    self.vertices.iter().map(|v| [v.uv.x(), v.uv.y()]).collect(),
+                ),
+                Column::Color => attributes::color(self.vertices.iter().map(|v| v.color).collect()),
+                Column::Other(attribute) => attribute.clone(),
+            });
+        }
+        let values = self
+            .triangles
+            .iter()
+            .flat_map(|t| t.0.iter().cloned())
+            .collect();
+        let like = if self.wide_indices {
+            Indices::U32(vec![])
+        } else {
+            Indices::U16(vec![])
+        };
+        mesh.indices = Some(indices_like(&like, values, self.vertices.len()));
+        mesh
+    }
+
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+    pub fn vertex(&self, vertex: u32) -> Option<&Vertex> {
+        self.vertices.get(vertex as usize)
+    }
+    pub fn vertex_mut(&mut self, vertex: u32) -> Option<&mut Vertex> {
+        self.vertices.get_mut(vertex as usize)
+    }
+    pub fn triangles(&self) -> &[Triangle] {
+        &self.triangles
+    }
+    pub fn triangle(&self, face: usize) -> Option<Triangle> {
+        self.triangles.get(face).cloned()
+    }
+    /// the edges of `face` in winding order
+    pub fn edges(&self, face: usize) -> Option<[(u32, u32); 3]> {
+        self.triangle(face).map(|t| t.edges())
+    }
+
+    /// appends a vertex, any attributes without a `Vertex` field get zeroes for it
+    pub fn add_vertex(&mut self, vertex: Vertex) -> u32 {
+        self.vertices.push(vertex);
+        for column in self.columns.iter_mut() {
+            if let Column::Other(attribute) = column {
+                attributes::push_zero(&mut attribute.values);
+            }
+        }
+        self.vertices.len() as u32 - 1
+    }
+    pub fn add_triangle(&mut self, triangle: Triangle) -> Result<usize, MeshieError> {
+        let vertex_count = self.vertices.len();
+        if let Some(i) = triangle.0.iter().find(|i| **i as usize >= vertex_count) {
+            return Err(MeshieError::OutOfBounds {
+                vertices: Range {
+                    start: *i as usize,
+                    end: *i as usize + 1,
+                },
+                vertex_count,
+            });
+        }
+        self.triangles.push(triangle);
+        Ok(self.triangles.len() - 1)
+    }
+    /// takes the face out, faces after it move down by one
+    pub fn remove_triangle(&mut self, face: usize) -> Option<Triangle> {
+        if face < self.triangles.len() {
+            Some(self.triangles.remove(face))
+        } else {
+            None
+        }
+    }
+    pub fn flip_triangle(&mut self, face: usize) {
+        if let Some(triangle) = self.triangles.get_mut(face) {
+            triangle.0.swap(0, 2);
+        }
+    }
+    /// drops every vertex no triangle uses, returning how many went
+    pub fn remove_unused_vertices(&mut self) -> usize {
+        let mut used = vec![false; self.vertices.len()];
+        for triangle in self.triangles.iter() {
+            for i in triangle.0.iter() {
+                used[*i as usize] = true;
+            }
+        }
+        let kept: Vec<usize> = (0..used.len()).filter(|i| used[*i]).collect();
+        let removed = used.len() - kept.len();
+        if removed == 0 {
+            return 0;
+        }
+
+        let mut moved = vec![0; used.len()];
+        for (new, old) in kept.iter().enumerate() {
+            moved[*old] = new as u32;
+        }
+        for triangle in self.triangles.iter_mut() {
+            for i in triangle.0.iter_mut() {
+                *i = moved[*i as usize];
+            }
+        }
+        self.vertices = kept.iter().map(|i| self.vertices[*i]).collect();
+        for column in self.columns.iter_mut() {
+            if let Column::Other(attribute) = column {
+                attribute.values = attributes::gather_values(&attribute.values, &kept);
+            }
+        }
+        removed
+    }
+
+    /// a snapshot of which faces touch each vertex and edge, it goes stale once triangles
+    /// are added or removed
+    pub fn adjacency(&self) -> Adjacency {
+        let mut vertex_faces = vec![Vec::new(); self.vertices.len()];
+        let mut edge_faces: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+        for (face, triangle) in self.triangles.iter().enumerate() {
+            for i in triangle.0.iter() {
+                vertex_faces[*i as usize].push(face);
+            }
+            for (a, b) in triangle.edges().iter() {
+                edge_faces.entry(edge_key(*a, *b)).or_default().push(face);
+            }
+        }
+        Adjacency {
+            triangles: self.triangles.clone(),
+            vertex_faces,
+            edge_faces,
+        }
+    }
+}
+
+/// Lookups between the vertices, edges and faces of an `EditMesh`.
+#[derive(Debug, Clone)]
+pub struct Adjacency {
+    triangles: Vec<Triangle>,
+    vertex_faces: Vec<Vec<usize>>,
+    edge_faces: HashMap<(u32, u32), Vec<usize>>,
 }
 
-pub(crate) struct Triangle {
+impl Adjacency {
+    /// the faces using `vertex`
+    pub fn faces_around(&self, vertex: u32) -> &[usize] {
+        self.vertex_faces
+            .get(vertex as usize)
+            .map_or(&[], |faces| faces.as_slice())
+    }
+    /// the faces running along the edge in either direction
+    pub fn faces_on_edge(&self, a: u32, b: u32) -> &[usize] {
+        self.edge_faces
+            .get(&edge_key(a, b))
+            .map_or(&[], |faces| faces.as_slice())
+    }
+    /// the faces sharing an edge with `face`
+    pub fn neighbours(&self, face: usize) -> Vec<usize> {
+        let mut neighbours: Vec<usize> = match self.triangles.get(face) {
+            Some(triangle) => triangle
+                .edges()
+                .iter()
+                .flat_map(|(a, b)| self.faces_on_edge(*a, *b).iter().cloned())
+                .filter(|other| *other != face)
+                .collect(),
+            None => return Vec::new(),
+        };
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        neighbours
+    }
+    /// the vertices joined to `vertex` by an edge
+    pub fn vertices_around(&self, vertex: u32) -> Vec<u32> {
+        let mut vertices: Vec<u32> = self
+            .faces_around(vertex)
+            .iter()
+            .flat_map(|face| self.triangles[*face].0.iter().cloned())
+            .filter(|other| *other != vertex)
+            .collect();
+        vertices.sort_unstable();
+        vertices.dedup();
+        vertices
+    }
+    /// edges used by a single face, the outline of an open surface
+    pub fn boundary_edges(&self) -> Vec<(u32, u32)> {
+        let mut edges: Vec<(u32, u32)> = self
+            .triangles
+            .iter()
+            .flat_map(|triangle| triangle.edges().to_vec())
+            .filter(|(a, b)| self.faces_on_edge(*a, *b).len() == 1)
+            .collect();
+        edges.sort_unstable();
+        edges
+    }
+}
 
+fn edge_key(a: u32, b: u32) -> (u32, u32) {
+    (a.min(b), a.max(b))
 }
 
-pub(crate) struct Vertex {
-    pub(crate) position: Vec3,
-    pub(crate) normal: Vec3,
-    pub(crate) uv: Vec2,
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Meshie;
+    use bevy::{math::vec2, prelude::shape, render::mesh::VertexAttributeValues};
+
+    fn quad() -> Mesh {
+        Mesh::from(shape::Quad {
+            size: vec2(2., 2.),
+            flip: false,
+        })
+    }
+
+    fn assert_same(a: &Mesh, b: &Mesh) {
+        assert_eq!(format!("{:?}", a), format!("{:?}", b));
+    }
+
+    #[test]
+    fn round_trips_losslessly() {
+        let mut mesh = quad();
+        mesh.add_mesh(&Mesh::from(shape::Cube { size: 1.0 }))
+            .unwrap();
+        mesh.set_colors(Range { start: 0, end: 4 }, [1.0, 0.0, 0.0, 1.0])
+            .unwrap();
+        mesh.attributes.insert(
+            1,
+            VertexAttribute {
+                name: "Vertex_Heat".into(),
+                values: VertexAttributeValues::Float((0..28).map(|i| i as f32).collect()),
+            },
+        );
+        assert_same(&EditMesh::from_mesh(&mesh).unwrap().to_mesh(), &mesh);
+
+        let mut small = quad();
+        small.indices = Some(Indices::U16(vec![0, 2, 1, 0, 3, 2]));
+        assert_same(&EditMesh::from_mesh(&small).unwrap().to_mesh(), &small);
+    }
+
+    #[test]
+    fn broken_meshes_are_refused() {
+        let mut mesh = quad();
+        mesh.indices = Some(Indices::U32(vec![0, 2, 1, 0, 3, 7]));
+        assert_eq!(
+            EditMesh::from_mesh(&mesh).unwrap_err(),
+            MeshieError::Invalid(MeshIssue::IndexOutOfRange {
+                triangle: 1,
+                index: 7
+            })
+        );
+        mesh.indices = None;
+        assert_eq!(
+            EditMesh::from_mesh(&mesh).unwrap_err(),
+            MeshieError::MissingIndices
+        );
+    }
+
+    #[test]
+    fn adjacency_of_a_quad() {
+        let edit = EditMesh::from_mesh(&quad()).unwrap();
+        let adjacency = edit.adjacency();
+        assert_eq!(adjacency.faces_around(0), &[0, 1]);
+        assert_eq!(adjacency.faces_around(1), &[0]);
+        assert_eq!(adjacency.faces_on_edge(2, 0), &[0, 1]);
+        assert_eq!(adjacency.neighbours(0), vec![1]);
+        assert_eq!(adjacency.vertices_around(1), vec![0, 2]);
+        assert_eq!(adjacency.vertices_around(0), vec![1, 2, 3]);
+        assert_eq!(
+            adjacency.boundary_edges(),
+            vec![(0, 3), (1, 0), (2, 1), (3, 2)]
+        );
+        assert_eq!(edit.edges(1), Some([(0, 3), (3, 2), (2, 0)]));
+    }
+
+    #[test]
+    fn edits_come_back_out() {
+        let mut mesh = quad();
+        mesh.attributes.push(VertexAttribute {
+            name: "Vertex_Heat".into(),
+            values: VertexAttributeValues::Float(vec![1.0, 2.0, 3.0, 4.0]),
+        });
+        let mut edit = EditMesh::from_mesh(&mesh).unwrap();
+        let top = edit.add_vertex(Vertex {
+            position: Vec3::new(0.0, 2.0, 0.0),
+            normal: Vec3::unit_z(),
+            ..Default::default()
+        });
+        assert_eq!(edit.add_triangle(Triangle([1, 2, top])), Ok(2));
+        assert!(edit.add_triangle(Triangle([1, 2, 9])).is_err());
+        edit.remove_triangle(1);
+        edit.vertex_mut(0).unwrap().uv = vec2(0.5, 0.5);
+        assert_eq!(edit.remove_unused_vertices(), 1);
+
+        let out = edit.to_mesh();
+        assert!(out.validate().unwrap().is_valid());
+        assert_eq!(
+            indices_to_u32(out.indices.as_ref().unwrap()),
+            vec![0, 2, 1, 1, 2, 3]
+        );
+        assert_eq!(attributes::uvs(&out).unwrap()[0], [0.5, 0.5]);
+        assert_eq!(attributes::positions(&out).unwrap()[3], [0.0, 2.0, 0.0]);
+        match attributes::attribute(&out, "Vertex_Heat").unwrap() {
+            VertexAttributeValues::Float(heat) => assert_eq!(heat, &vec![1.0, 2.0, 3.0, 0.0]),
+            _ => panic!("heat changed type"),
+        }
+    }
+}
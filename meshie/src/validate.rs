@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+
+use bevy::{math::Vec3, prelude::Mesh, render::mesh::Indices};
+
+use crate::{attributes, indices_like, indices_to_u32, MeshieError};
+
+/// Something wrong with a mesh. Triangles are numbered by their position in the index
+/// buffer, so triangle `t` is made of indices `3 * t..3 * t + 3`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeshIssue {
+    /// the triangle uses a vertex past the end of the mesh
+    IndexOutOfRange { triangle: usize, index: usize },
+    /// the index count isn't a multiple of three, this many indices are left over
+    TrailingIndices(usize),
+    /// the attribute holds a different number of values than there are positions
+    AttributeLength { name: String, len: usize },
+    /// the triangle repeats a vertex or has all three corners on a line
+    DegenerateTriangle(usize),
+    /// the vertex has a NaN coordinate
+    NanPosition(usize),
+    /// both triangles run along the same edge in the same direction, so one of them is
+    /// facing the wrong way
+    InconsistentWinding { triangles: [usize; 2] },
+}
+
+/// Every issue found by `Meshie::validate`, in the order they were found.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ValidationReport {
+    pub vertex_count: usize,
+    pub triangle_count: usize,
+    pub issues: Vec<MeshIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+    /// triangles that `repair` would drop
+    pub fn bad_triangles(&self) -> Vec<usize> {
+        let mut triangles: Vec<usize> = self
+            .issues
+            .iter()
+            .filter_map(|issue| match issue {
+                MeshIssue::IndexOutOfRange { triangle, .. } => Some(*triangle),
+                MeshIssue::DegenerateTriangle(triangle) => Some(*triangle),
+                _ => None,
+            })
+            .collect();
+        triangles.sort_unstable();
+        triangles.dedup();
+        triangles
+    }
+}
+
+pub(crate) fn validate(mesh: &Mesh) -> Result<ValidationReport, MeshieError> {
+    validate_from(mesh, 0, 0)
+}
+
+/// like `validate`, but only looks at vertices and triangles from `first_vertex` and
+/// `first_triangle` on. used to check what `add_mesh` appended without going over the
+/// whole mesh again
+pub(crate) fn validate_from(
+    mesh: &Mesh,
+    first_vertex: usize,
+    first_triangle: usize,
+) -> Result<ValidationReport, MeshieError> {
+    let positions = attributes::positions(mesh)?;
+    let mut report = ValidationReport {
+        vertex_count: positions.len(),
+        ..Default::default()
+    };
+    for attribute in mesh.attributes.iter() {
+        if attribute.values.len() != positions.len() {
+            report.issues.push(MeshIssue::AttributeLength {
+                name: attribute.name.to_string(),
+                len: attribute.values.len(),
+            });
+        }
+    }
+    for (i, position) in positions.iter().enumerate().skip(first_vertex) {
+        if position.iter().any(|p| p.is_nan()) {
+            report.issues.push(MeshIssue::NanPosition(i));
+        }
+    }
+
+    let start = first_triangle * 3;
+    let values: Vec<u32> = match mesh.indices.as_ref() {
+        Some(Indices::U16(values)) => values[start..].iter().map(|i| *i as u32).collect(),
+        Some(Indices::U32(values)) => values[start..].to_vec(),
+        None => return Ok(report),
+    };
+    report.triangle_count = first_triangle + values.len() / 3;
+    if values.len() % 3 != 0 {
+        report
+            .issues
+            .push(MeshIssue::TrailingIndices(values.len() % 3));
+    }
+    // first triangle seen running along each directed edge
+    let mut edges: HashMap<(u32, u32), usize> = HashMap::new();
+    for (t, triangle) in values.chunks_exact(3).enumerate() {
+        let t = first_triangle + t;
+        if let Some(index) = triangle.iter().find(|i| **i as usize >= positions.len()) {
+            report.issues.push(MeshIssue::IndexOutOfRange {
+                triangle: t,
+                index: *index as usize,
+            });
+            continue;
+        }
+        let corners = [
+            Vec3::from(positions[triangle[0] as usize]),
+            Vec3::from(positions[triangle[1] as usize]),
+            Vec3::from(positions[triangle[2] as usize]),
+        ];
+        if is_degenerate(triangle, &corners) {
+            report.issues.push(MeshIssue::DegenerateTriangle(t));
+            continue;
+        }
+        for (a, b) in [(0, 1), (1, 2), (2, 0)].iter() {
+            let edge = (triangle[*a], triangle[*b]);
+            match edges.get(&edge) {
+                Some(other) => report.issues.push(MeshIssue::InconsistentWinding {
+                    triangles: [*other, t],
+                }),
+                None => {
+                    edges.insert(edge, t);
+                }
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// drops every triangle that points past the end of the mesh, is degenerate or touches a NaN
+/// position, along with any trailing indices. winding and attribute lengths are left alone as
+/// there's no telling which side is wrong. returns the number of triangles dropped
+pub(crate) fn repair(mesh: &mut Mesh) -> Result<usize, MeshieError> {
+    let report = validate(mesh)?;
+    let nan: Vec<usize> = report
+        .issues
+        .iter()
+        .filter_map(|issue| match issue {
+            MeshIssue::NanPosition(i) => Some(*i),
+            _ => None,
+        })
+        .collect();
+    let bad = report.bad_triangles();
+    let indices = match mesh.indices.as_ref() {
+        Some(indices) => indices,
+        None => return Ok(0),
+    };
+    let values = indices_to_u32(indices);
+    let mut kept = Vec::with_capacity(values.len());
+    let mut dropped = 0;
+    for (t, triangle) in values.chunks_exact(3).enumerate() {
+        let touches_nan = triangle
+            .iter()
+            .any(|i| nan.binary_search(&(*i as usize)).is_ok());
+        if touches_nan || bad.binary_search(&t).is_ok() {
+            dropped += 1;
+        } else {
+            kept.extend_from_slice(triangle);
+        }
+    }
+    mesh.indices = Some(indices_like(indices, kept, report.vertex_count));
+    Ok(dropped)
+}
+
+fn is_degenerate(triangle: &[u32], corners: &[Vec3; 3]) -> bool {
+    if triangle[0] == triangle[1] || triangle[1] == triangle[2] || triangle[2] == triangle[0] {
+        return true;
+    }
+    let ab = corners[1] - corners[0];
+    let ac = corners[2] - corners[0];
+    let longest = ab.length_squared().max(ac.length_squared());
+    // the cross product shrinks with the square of the edges, compare it to their scale
+    ab.cross(ac).length() <= longest * 1e-6
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Meshie;
+    use bevy::{math::vec2, prelude::shape, render::mesh::VertexAttributeValues};
+
+    fn quad() -> Mesh {
+        Mesh::from(shape::Quad {
+            size: vec2(2., 2.),
+            flip: false,
+        })
+    }
+
+    #[test]
+    fn shapes_are_valid() {
+        let report = quad().validate().unwrap();
+        assert!(report.is_valid(), "{:?}", report.issues);
+        assert_eq!(report.triangle_count, 2);
+        let cube = Mesh::from(shape::Cube { size: 1.0 });
+        assert!(cube.validate().unwrap().is_valid());
+    }
+
+    #[test]
+    fn finds_every_kind_of_issue() {
+        let mut mesh = quad();
+        mesh.indices = Some(Indices::U32(vec![
+            0, 2, 1, // fine
+            0, 3, 2, // fine
+            0, 1, 2, // runs from 2 to 0 like the second triangle
+            0, 0, 1, // repeats a vertex
+            0, 9, 1, // out of range
+            3, 0,
+        ]));
+        attributes::positions_mut(&mut mesh).unwrap()[3][1] = std::f32::NAN;
+        if let VertexAttributeValues::Float2(uvs) =
+            attributes::attribute_mut(&mut mesh, "Vertex_Uv").unwrap()
+        {
+            uvs.pop();
+        }
+        let issues = mesh.validate().unwrap().issues;
+        assert!(issues.contains(&MeshIssue::AttributeLength {
+            name: "Vertex_Uv".to_string(),
+            len: 3
+        }));
+        assert!(issues.contains(&MeshIssue::NanPosition(3)));
+        assert!(issues.contains(&MeshIssue::TrailingIndices(2)));
+        assert!(issues.contains(&MeshIssue::InconsistentWinding { triangles: [1, 2] }));
+        assert!(issues.contains(&MeshIssue::DegenerateTriangle(3)));
+        assert!(issues.contains(&MeshIssue::IndexOutOfRange {
+            triangle: 4,
+            index: 9
+        }));
+    }
+
+    #[test]
+    fn collinear_triangles_are_degenerate() {
+        let mut mesh = quad();
+        // the second triangle is now sw, se and the midpoint between them
+        attributes::positions_mut(&mut mesh).unwrap()[2] = [0.0, -1.0, 0.0];
+        let report = mesh.validate().unwrap();
+        assert_eq!(report.issues, vec![MeshIssue::DegenerateTriangle(1)]);
+    }
+
+    #[test]
+    fn repair_drops_bad_triangles() {
+        let mut mesh = quad();
+        mesh.add_mesh(&quad()).unwrap();
+        mesh.indices = Some(Indices::U16(vec![
+            0, 2, 1, 0, 3, 2, 4, 6, 5, 4, 4, 5, 4, 12, 6, 5, 6, 7, 7, 6,
+        ]));
+        attributes::positions_mut(&mut mesh).unwrap()[7][0] = std::f32::NAN;
+        assert_eq!(mesh.repair(), Ok(3));
+        assert!(matches!(mesh.indices, Some(Indices::U16(_))));
+        assert_eq!(
+            indices_to_u32(mesh.indices.as_ref().unwrap()),
+            vec![0, 2, 1, 0, 3, 2, 4, 6, 5]
+        );
+        let report = mesh.validate().unwrap();
+        assert_eq!(report.issues, vec![MeshIssue::NanPosition(7)]);
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use bevy::{math::Vec3, prelude::Mesh};
+use ds_range::Range;
+
+use crate::{attributes, indices_like, indices_to_u32, MeshieError};
+
+/// Merges the vertices in `vertices` whose position, normal and uv are all within `epsilon`
+/// of each other, keeping the first of each group along with its other attributes.
+///
+/// Triangles are pointed at the vertex that was kept and any that collapse onto a repeated
+/// vertex are dropped. The range shrinks by the number of vertices removed and every vertex
+/// after it moves down by the same amount. Positions are bucketed into `epsilon` sized cells so
+/// only nearby vertices are compared.
+pub(crate) fn weld(mesh: &mut Mesh, vertices: Range, epsilon: f32) -> Result<usize, MeshieError> {
+    attributes::check_range(mesh, vertices)?;
+    let indices = mesh.indices.as_ref().ok_or(MeshieError::MissingIndices)?;
+    let positions = attributes::positions(mesh)?;
+    let normals = attributes::normals(mesh).ok();
+    let uvs = attributes::uvs(mesh).ok();
+    let vertex_count = positions.len();
+
+    let matches = |a: usize, b: usize| {
+        let close = |x: &[f32], y: &[f32]| x.iter().zip(y).all(|(x, y)| (x - y).abs() <= epsilon);
+        close(&positions[a], &positions[b])
+            && normals.map_or(true, |n| close(&n[a], &n[b]))
+            && uvs.map_or(true, |uv| close(&uv[a], &uv[b]))
+    };
+    let cell_size = epsilon.max(std::f32::EPSILON);
+    let cell = |i: usize| {
+        let p = Vec3::from(positions[i]) / cell_size;
+        (
+            p.x().floor() as i64,
+            p.y().floor() as i64,
+            p.z().floor() as i64,
+        )
+    };
+
+    // the vertex each one is merged into, which is itself when it's kept
+    let mut merged_into: Vec<usize> = (0..vertex_count).collect();
+    let mut kept_in_cell: HashMap<(i64, i64, i64), Vec<usize>> = HashMap::new();
+    for i in vertices.iter() {
+        let (x, y, z) = cell(i);
+        let mut found = None;
+        'search: for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    if let Some(kept) = kept_in_cell.get(&(x + dx, y + dy, z + dz)) {
+                        if let Some(k) = kept.iter().find(|k| matches(**k, i)) {
+                            found = Some(*k);
+                            break 'search;
+                        }
+                    }
+                }
+            }
+        }
+        match found {
+            Some(k) => merged_into[i] = k,
+            None => kept_in_cell.entry((x, y, z)).or_default().push(i),
+        }
+    }
+
+    let kept: Vec<usize> = (0..vertex_count)
+        .filter(|i| merged_into[*i] == *i)
+        .collect();
+    let removed = vertex_count - kept.len();
+    if removed == 0 {
+        return Ok(0);
+    }
+    let mut moved = vec![0; vertex_count];
+    for (new, old) in kept.iter().enumerate() {
+        moved[*old] = new as u32;
+    }
+    let mut values = Vec::new();
+    for triangle in indices_to_u32(indices).chunks_exact(3) {
+        let t: Vec<u32> = triangle
+            .iter()
+            .map(|i| moved[merged_into[*i as usize]])
+            .collect();
+        if t[0] != t[1] && t[1] != t[2] && t[2] != t[0] {
+            values.extend(t);
+        }
+    }
+
+    mesh.indices = Some(indices_like(indices, values, kept.len()));
+    for attribute in mesh.attributes.iter_mut() {
+        attribute.values = attributes::gather_values(&attribute.values, &kept);
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Meshie;
+    use bevy::{
+        math::{vec2, vec3},
+        prelude::shape,
+        render::mesh::Indices,
+    };
+
+    fn quad() -> Mesh {
+        Mesh::from(shape::Quad {
+            size: vec2(2., 2.),
+            flip: false,
+        })
+    }
+
+    /// two quads side by side with matching uvs along the edge they share
+    fn strip() -> Mesh {
+        let mut mesh = quad();
+        let right = mesh.add_mesh(&quad()).unwrap();
+        mesh.translate_mesh(right, vec3(2.0, 0.0, 0.0)).unwrap();
+        let all = attributes::vertex_range(&mesh).unwrap();
+        mesh.set_uvs(all, vec![[0.5, 0.5]; 8]).unwrap();
+        mesh
+    }
+
+    #[test]
+    fn shared_edge_is_welded() {
+        let mut mesh = strip();
+        let all = attributes::vertex_range(&mesh).unwrap();
+        assert_eq!(mesh.weld(all, 0.0), Ok(2));
+        assert_eq!(attributes::vertex_count(&mesh), Ok(6));
+        assert_eq!(
+            indices_to_u32(mesh.indices.as_ref().unwrap()),
+            vec![0, 2, 1, 0, 3, 2, 3, 4, 2, 3, 5, 4]
+        );
+        assert!(mesh.validate().unwrap().is_valid());
+    }
+
+    #[test]
+    fn epsilon_decides_what_matches() {
+        let mut mesh = strip();
+        attributes::positions_mut(&mut mesh).unwrap()[4][0] += 0.0005;
+        let all = attributes::vertex_range(&mesh).unwrap();
+        assert_eq!(mesh.weld(all, 0.0001), Ok(1));
+        let all = attributes::vertex_range(&mesh).unwrap();
+        assert_eq!(mesh.weld(all, 0.001), Ok(1));
+
+        // different uvs keep vertices apart no matter where they are
+        let mut mesh = quad();
+        mesh.add_mesh(&quad()).unwrap();
+        let all = attributes::vertex_range(&mesh).unwrap();
+        assert_eq!(mesh.weld(all, 0.001), Ok(4));
+        let mut mesh = strip();
+        let all = attributes::vertex_range(&mesh).unwrap();
+        attributes::uvs_mut(&mut mesh).unwrap()[4] = [0.0, 0.0];
+        assert_eq!(mesh.weld(all, 0.001), Ok(1));
+    }
+
+    #[test]
+    fn only_the_range_is_welded() {
+        let mut mesh = strip();
+        mesh.add_mesh(&quad()).unwrap();
+        // the third quad sits on top of the first but is left alone
+        assert_eq!(mesh.weld(Range { start: 0, end: 8 }, 0.0), Ok(2));
+        assert_eq!(attributes::vertex_count(&mesh), Ok(10));
+        assert_eq!(
+            indices_to_u32(mesh.indices.as_ref().unwrap())[12..].to_vec(),
+            vec![6, 8, 7, 6, 9, 8]
+        );
+        assert_eq!(
+            mesh.get_positions(Range { start: 6, end: 10 }),
+            attributes::positions(&quad()).map(|p| p.clone())
+        );
+    }
+
+    #[test]
+    fn welding_keeps_u16_indices() {
+        let quad_u16 = || {
+            let mut mesh = quad();
+            mesh.indices = Some(Indices::U16(vec![0, 2, 1, 0, 3, 2]));
+            mesh
+        };
+        let mut mesh = quad_u16();
+        let copy = mesh.add_mesh(&quad_u16()).unwrap();
+        assert!(matches!(mesh.indices, Some(Indices::U16(_))));
+        assert_eq!(mesh.weld(copy, 0.0), Ok(0));
+        let all = attributes::vertex_range(&mesh).unwrap();
+        assert_eq!(mesh.weld(all, 0.0), Ok(4));
+        assert!(matches!(mesh.indices, Some(Indices::U16(_))));
+    }
+}
@@ -11,7 +11,7 @@ struct Light {
 layout(location = 0) in vec3 v_Position;
 layout(location = 1) in vec3 v_Normal;
 layout(location = 2) in vec2 v_Uv;
-// layout(location = 3) in vec4 v_Color;
+layout(location = 3) in vec4 v_Color;
 // layout(location = 4) in float v_Texture;
 layout(location = 5) in float v_Lod;
 
@@ -52,7 +52,7 @@ layout(set = 3, binding = 11) uniform sampler MeshMaterial_texture5_sampler;
 # endif
 
 void main() {
-    vec4 output_color = Albedo;
+    vec4 output_color = Albedo * v_Color;
 if (v_Lod > 2000.0) {
     # ifdef MESHMATERIAL_TEXTURE2
         output_color *= texture(
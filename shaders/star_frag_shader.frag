@@ -13,6 +13,7 @@ struct Light {
 layout(location = 0) in vec3 v_Position;
 layout(location = 1) in vec3 v_Normal;
 layout(location = 2) in vec2 v_Uv;
+layout(location = 3) in vec4 v_Color;
 
 layout(location = 0) out vec4 o_Target;
 
@@ -68,7 +69,7 @@ void main() {
     // if (output_color[0] > 0.7) {
 
 
-    o_Target = output_color * vec4(0.7, 0.7, 0.7, 1.0);
+    o_Target = output_color * v_Color * vec4(0.7, 0.7, 0.7, 1.0);
 
 
     // } else {